@@ -0,0 +1,257 @@
+use super::required_time::DayType;
+use super::*;
+
+/// The kinds of absences which reduce the required time
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum AbsenceKind {
+    Holiday,
+    Vacation,
+    Sick,
+    JobTravel,
+    OvertimeReduction,
+}
+
+impl std::fmt::Display for AbsenceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match *self {
+            AbsenceKind::Holiday => "Holiday",
+            AbsenceKind::Vacation => "Vacation",
+            AbsenceKind::Sick => "Sick",
+            AbsenceKind::JobTravel => "JobTravel",
+            AbsenceKind::OvertimeReduction => "OvertimeReduction",
+        };
+        // pad like a string, such that the width of the formatter is respected
+        f.pad(name)
+    }
+}
+
+/// The kind, the number of days and the description of the absence of a day. Half days of vacation
+/// count as 1/2 day of vacation.
+fn absence_of(day_type: &DayType) -> Option<(AbsenceKind, f64, &str)> {
+    match *day_type {
+        DayType::WorkDay | DayType::WeekEnd => None,
+        DayType::Holiday { ref name } => Some((AbsenceKind::Holiday, 1.0, name)),
+        DayType::Vacation { ref description } => Some((AbsenceKind::Vacation, 1.0, description)),
+        DayType::VacationHalfDay { ref description } => {
+            Some((AbsenceKind::Vacation, 0.5, description))
+        }
+        DayType::Sick { ref description } => Some((AbsenceKind::Sick, 1.0, description)),
+        DayType::JobTravel { ref description } => Some((AbsenceKind::JobTravel, 1.0, description)),
+        DayType::OvertimeReduction { ref description } => {
+            Some((AbsenceKind::OvertimeReduction, 1.0, description))
+        }
+    }
+}
+
+/// Consecutive days of the same absence. Free days in between don't interrupt it, but they are
+/// not counted either.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AbsencePeriod {
+    pub kind: AbsenceKind,
+    pub description: String,
+    pub first_date: Date,
+    pub last_date: Date,
+    pub days: f64,
+}
+
+impl std::fmt::Display for AbsencePeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} - {} {:18} {:>5} {}",
+            self.first_date.format("%F"),
+            self.last_date.format("%F"),
+            self.kind,
+            self.days,
+            self.description
+        )
+    }
+}
+
+pub type AbsenceCount = std::collections::BTreeMap<AbsenceKind, f64>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AbsenceReport {
+    pub start_date: Date,
+    pub end_date: Date,
+    /// The absences per month (e.g. '2018-05')
+    pub per_month: std::collections::BTreeMap<String, AbsenceCount>,
+    pub total: AbsenceCount,
+    pub periods: Vec<AbsencePeriod>,
+}
+
+/// Counts the absences on the work days of the schedule
+pub fn compute_absence_report(
+    required_times: &[required_time::RequiredTime],
+    schedules: &schedule::ScheduleSeries,
+) -> Option<AbsenceReport> {
+    let mut ret = AbsenceReport {
+        start_date: required_times.first()?.date,
+        end_date: required_times.last()?.date,
+        per_month: std::collections::BTreeMap::new(),
+        total: AbsenceCount::new(),
+        periods: Vec::new(),
+    };
+    let mut current_period: Option<AbsencePeriod> = None;
+    for required_time in required_times {
+        let date = required_time.date;
+        if !schedules.at(&date).is_work_day(&date) {
+            continue;
+        }
+        let (kind, days, description) = match absence_of(&required_time.day_type) {
+            Some(absence) => absence,
+            None => {
+                ret.periods.extend(current_period.take());
+                continue;
+            }
+        };
+        *ret.per_month
+            .entry(report::Period::Month.label(&date))
+            .or_default()
+            .entry(kind)
+            .or_insert(0.0) += days;
+        *ret.total.entry(kind).or_insert(0.0) += days;
+        match current_period {
+            Some(ref mut period) if period.kind == kind && period.description == description => {
+                period.last_date = date;
+                period.days += days;
+            }
+            _ => {
+                ret.periods.extend(current_period.replace(AbsencePeriod {
+                    kind,
+                    description: description.to_string(),
+                    first_date: date,
+                    last_date: date,
+                    days,
+                }));
+            }
+        }
+    }
+    ret.periods.extend(current_period);
+    Some(ret)
+}
+
+fn write_count(f: &mut std::fmt::Formatter, count: &AbsenceCount) -> std::fmt::Result {
+    for (kind, days) in count {
+        writeln!(f, "{:20}: {:>8}", kind, days)?;
+    }
+    Ok(())
+}
+
+impl std::fmt::Display for AbsenceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "= Absences from {} to {}:",
+            self.start_date.format("%F"),
+            self.end_date.format("%F")
+        )?;
+        for (month, count) in &self.per_month {
+            writeln!(f, "== {}:", month)?;
+            write_count(f, count)?;
+        }
+        writeln!(f, "== Total:")?;
+        write_count(f, &self.total)?;
+        write!(f, "== Periods:")?;
+        for period in &self.periods {
+            write!(f, "\n{}", period)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_absence_report() {
+        // 2018-05-04 is a friday
+        let entries = required_time::parse_required_time(
+            &mut std::io::BufReader::new(
+                r"2018-05-03--2018-05-07 -- K Grippe
+2018-05-08 -- H Arzt
+2018-05-10 -- F Christi Himmelfahrt
+2018-05-11 -- U Brückentag
+2018-05-31--2018-06-01 -- W Konferenz: Berlin"
+                    .as_bytes(),
+            ),
+            "tst_file",
+        )
+        .unwrap();
+        let schedules: schedule::ScheduleSeries =
+            schedule::Schedule::from_duration_of_day(chrono::Duration::hours(8)).into();
+        let required_times = required_time::consolidate_required_time(
+            &entries,
+            &Date::from_ymd_opt(2018, 5, 1).unwrap(),
+            &Date::from_ymd_opt(2018, 6, 30).unwrap(),
+            &schedules,
+        )
+        .unwrap();
+        let report = compute_absence_report(&required_times, &schedules).unwrap();
+
+        let may = &report.per_month["2018-05"];
+        assert_eq!(may[&AbsenceKind::Sick], 3.0);
+        assert_eq!(may[&AbsenceKind::Vacation], 1.5);
+        assert_eq!(may[&AbsenceKind::Holiday], 1.0);
+        assert_eq!(may[&AbsenceKind::JobTravel], 1.0);
+        assert_eq!(report.per_month["2018-06"][&AbsenceKind::JobTravel], 1.0);
+        assert_eq!(report.total[&AbsenceKind::JobTravel], 2.0);
+
+        let periods: Vec<_> = report
+            .periods
+            .iter()
+            .map(|period| {
+                (
+                    period.first_date.format("%d").to_string(),
+                    period.last_date.format("%d").to_string(),
+                    period.kind,
+                    period.days,
+                )
+            })
+            .collect();
+        assert_eq!(
+            periods,
+            vec![
+                // the weekend does not interrupt the sickness
+                ("03".to_string(), "07".to_string(), AbsenceKind::Sick, 3.0),
+                (
+                    "08".to_string(),
+                    "08".to_string(),
+                    AbsenceKind::Vacation,
+                    0.5
+                ),
+                (
+                    "10".to_string(),
+                    "10".to_string(),
+                    AbsenceKind::Holiday,
+                    1.0
+                ),
+                (
+                    "11".to_string(),
+                    "11".to_string(),
+                    AbsenceKind::Vacation,
+                    1.0
+                ),
+                (
+                    "31".to_string(),
+                    "01".to_string(),
+                    AbsenceKind::JobTravel,
+                    2.0
+                ),
+            ]
+        );
+        assert_eq!(report.periods[4].description, "Konferenz");
+    }
+
+    #[test]
+    fn test_absence_report_without_days() {
+        assert_eq!(
+            compute_absence_report(
+                &Vec::new(),
+                &schedule::Schedule::from_duration_of_day(chrono::Duration::hours(8)).into()
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use super::work_day;
+use super::*;
+
+/// The way an alias recognizes the keys it replaces
+#[derive(Clone, Debug)]
+enum Pattern {
+    Key(String),
+    /// Has to match the whole key. The replacement may refer to its groups (e.g. '$1').
+    Regex(regex::Regex),
+}
+
+#[derive(Clone, Debug)]
+struct Alias {
+    pattern: Pattern,
+    replacement: String,
+}
+
+/// Replaces free-text keys of the .work-files (e.g. 'Standup') by JIRA issues. The aliases are
+/// tried in the order of the alias file and the first matching one wins.
+#[derive(Clone, Debug, Default)]
+pub struct AliasTable {
+    aliases: Vec<Alias>,
+}
+
+impl AliasTable {
+    /// The replacement of the key, if any alias matches it
+    pub fn lookup(&self, key: &str) -> Option<String> {
+        self.aliases.iter().find_map(|alias| match alias.pattern {
+            Pattern::Key(ref k) if k == key => Some(alias.replacement.clone()),
+            Pattern::Key(_) => None,
+            Pattern::Regex(ref re) => re.captures(key).map(|c| {
+                let mut replacement = String::new();
+                c.expand(&alias.replacement, &mut replacement);
+                replacement
+            }),
+        })
+    }
+
+    /// Replaces the key of the entry keeping the original one in `alias`. Keys naming several
+    /// tickets are replaced ticket by ticket keeping their weights. Only keys counting as work are
+    /// replaced, breaks and excluded keys would turn into work.
+    pub fn apply(&self, entry: &mut work_day::Entry, key_classes: &work_day::KeyClasses) {
+        let keys = work_day::split_key(&entry.key);
+        let mut replaced = false;
+        let new_keys: Vec<String> = keys
+            .iter()
+            .map(|&(key, weight)| {
+                let replacement = if key_classes.is_work(key) {
+                    self.lookup(key)
+                } else {
+                    None
+                };
+                let key = match replacement {
+                    Some(replacement) => {
+                        replaced = true;
+                        replacement
+                    }
+                    None => key.to_string(),
+                };
+                if weight != 1 {
+                    format!("{}*{}", key, weight)
+                } else {
+                    key
+                }
+            })
+            .collect();
+        if replaced {
+            entry.alias = Some(std::mem::replace(&mut entry.key, new_keys.join("|")));
+        }
+    }
+
+    pub fn apply_to_day(
+        &self,
+        work_day: &mut work_day::WorkDay,
+        key_classes: &work_day::KeyClasses,
+    ) {
+        for entry in &mut work_day.entries {
+            self.apply(entry, key_classes);
+        }
+    }
+}
+
+/// The original keys of the .work-files by the keys which replaced them through aliases
+#[derive(Debug, Default)]
+pub struct AliasSources(std::collections::BTreeMap<String, std::collections::BTreeSet<String>>);
+
+impl AliasSources {
+    pub fn from_entries<'a>(
+        entries: impl IntoIterator<Item = &'a work_day::Entry>,
+    ) -> AliasSources {
+        let mut ret = AliasSources::default();
+        for entry in entries.into_iter().flat_map(|entry| entry.split()) {
+            if let Some(alias) = entry.alias {
+                ret.0.entry(entry.key).or_default().insert(alias);
+            }
+        }
+        ret
+    }
+
+    /// A note for the report which aliases yielded the key (empty if there were none)
+    pub fn describe(&self, key: &str) -> String {
+        match self.0.get(key) {
+            Some(aliases) => format!(" (alias of {})", itertools::join(aliases, ", ")),
+            None => String::new(),
+        }
+    }
+}
+
+/// Parses an alias file. Each line has the form 'Key -> ISSUE-1' or '/regex/ -> ISSUE-1'. Empty
+/// lines and lines starting with '#' are ignored.
+pub fn parse_aliases(stream: &mut dyn std::io::BufRead, file_name: &str) -> Result<AliasTable> {
+    lazy_static::lazy_static! {
+        static ref RE: regex::Regex = regex::Regex::new(r"^(/(.+)/|(.+?)) *-> *([^ ]+)$")
+            .expect("Erronuous Regular Expression for alias parsing");
+    }
+    let mut ret = AliasTable::default();
+    let mut line_nr = 0u32;
+    loop {
+        let mut line = String::new();
+        let bytes_read = stream.read_line(&mut line)?;
+        if bytes_read == 0 {
+            return Ok(ret);
+        }
+        line_nr += 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = || Error::ParseAlias {
+            file: file_name.to_string(),
+            line_nr,
+        };
+        let c = RE.captures(line).ok_or_else(error)?;
+        let pattern = match (c.get(2), c.get(3)) {
+            (Some(re), _) => Pattern::Regex(
+                regex::Regex::new(&format!("^(?:{})$", re.as_str())).map_err(|_| error())?,
+            ),
+            (None, Some(key)) => Pattern::Key(key.as_str().to_string()),
+            (None, None) => return Err(error()),
+        };
+        ret.aliases.push(Alias {
+            pattern,
+            replacement: c[4].to_string(),
+        });
+    }
+}
+
+pub fn parse_alias_file(file_name: &std::path::PathBuf) -> Result<AliasTable> {
+    let file = std::fs::File::open(file_name)?;
+    let mut fstream = std::io::BufReader::new(file);
+    let file_name_str = match file_name.to_str() {
+        Some(fi) => fi,
+        None => {
+            return Err(Error::InvalidFileName {
+                file: file_name.clone(),
+            })
+        }
+    };
+    parse_aliases(&mut fstream, file_name_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_table() -> AliasTable {
+        let txt = "# team meetings\nStandup -> TEAM-42\n\n/Review (PROJ-\\d+)/ -> $1\n/Sup.*/ -> TEAM-7\n";
+        parse_aliases(&mut std::io::BufReader::new(txt.as_bytes()), "aliases").unwrap()
+    }
+
+    fn make_entry(key: &str) -> work_day::Entry {
+        work_day::Entry {
+            start_ts: Time::from_hms_opt(9, 0, 0).unwrap(),
+            duration: chrono::Duration::minutes(30),
+            key: key.to_string(),
+            sub_keys: Vec::new(),
+            alias: None,
+            raw_data: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        let table = make_table();
+        assert_eq!(table.lookup("Standup"), Some("TEAM-42".to_string()));
+        assert_eq!(table.lookup("Review PROJ-3"), Some("PROJ-3".to_string()));
+        assert_eq!(table.lookup("Support"), Some("TEAM-7".to_string()));
+        assert_eq!(table.lookup("Standups"), None);
+        assert_eq!(table.lookup("ASupport"), None);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let txt = "Standup -> TEAM-42\nStandup\n";
+        let result = parse_aliases(&mut std::io::BufReader::new(txt.as_bytes()), "aliases");
+        assert_eq!(
+            result.unwrap_err(),
+            Error::ParseAlias {
+                file: "aliases".to_string(),
+                line_nr: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let table = make_table();
+        let mut entry = make_entry("Standup");
+        table.apply(&mut entry, &work_day::KeyClasses::default());
+        assert_eq!(entry.key, "TEAM-42");
+        assert_eq!(entry.alias, Some("Standup".to_string()));
+
+        let mut entry = make_entry("PROJ-1");
+        table.apply(&mut entry, &work_day::KeyClasses::default());
+        assert_eq!(entry.key, "PROJ-1");
+        assert_eq!(entry.alias, None);
+    }
+
+    #[test]
+    fn test_apply_to_multiple_keys() {
+        let table = make_table();
+        let mut entry = make_entry("Standup*2|PROJ-1");
+        table.apply(&mut entry, &work_day::KeyClasses::default());
+        assert_eq!(entry.key, "TEAM-42*2|PROJ-1");
+        let entries: Vec<_> = entry
+            .split()
+            .into_iter()
+            .map(|entry| (entry.key, entry.duration.num_minutes(), entry.alias))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("TEAM-42".to_string(), 20, Some("Standup".to_string())),
+                ("PROJ-1".to_string(), 10, None)
+            ]
+        );
+        let sources = AliasSources::from_entries(&[entry]);
+        assert_eq!(sources.describe("TEAM-42"), " (alias of Standup)");
+        assert_eq!(sources.describe("PROJ-1"), "");
+    }
+
+    #[test]
+    fn test_apply_replacement_with_multiple_keys() {
+        let txt = "Standup -> TEAM-1|TEAM-2\n";
+        let table = parse_aliases(&mut std::io::BufReader::new(txt.as_bytes()), "aliases").unwrap();
+        let mut entry = make_entry("Standup|PROJ-1");
+        table.apply(&mut entry, &work_day::KeyClasses::default());
+        assert_eq!(entry.key, "TEAM-1|TEAM-2|PROJ-1");
+        let sources = AliasSources::from_entries(&[entry]);
+        assert_eq!(sources.describe("TEAM-1"), " (alias of Standup)");
+        assert_eq!(sources.describe("TEAM-2"), " (alias of Standup)");
+        assert_eq!(sources.describe("PROJ-1"), "");
+    }
+
+    #[test]
+    fn test_non_work_keys_are_not_replaced() {
+        let txt = "/.*/ -> TEAM-1\n";
+        let table = parse_aliases(&mut std::io::BufReader::new(txt.as_bytes()), "aliases").unwrap();
+        let key_classes =
+            work_day::KeyClasses::new(vec!["Break".to_string()], vec!["Doctor".to_string()]);
+        for key in ["Break", "Doctor"] {
+            let mut entry = make_entry(key);
+            table.apply(&mut entry, &key_classes);
+            assert_eq!(entry.key, key);
+            assert_eq!(entry.alias, None);
+        }
+    }
+}
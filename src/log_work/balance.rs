@@ -0,0 +1,112 @@
+use super::work_day;
+use super::*;
+
+/// The flextime account ("Gleitzeitkonto"). Starting with an opening balance at a given date, the
+/// difference between the worked and the required time of every following day is carried forward.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FlextimeAccount {
+    start_date: Option<Date>,
+    balance: chrono::Duration,
+}
+
+impl FlextimeAccount {
+    /// Days before `start_date` are not booked. Without a start date every day is booked.
+    pub fn new(start_date: Option<Date>, opening_balance: chrono::Duration) -> FlextimeAccount {
+        FlextimeAccount {
+            start_date,
+            balance: opening_balance,
+        }
+    }
+
+    /// Adds the difference of the day to the account and returns the new balance. Returns `None`
+    /// if the day lies before the start date of the account.
+    pub fn book_day(
+        &mut self,
+        day: &work_day::Day,
+        key_classes: &work_day::KeyClasses,
+    ) -> Option<chrono::Duration> {
+        if let Some(start_date) = self.start_date {
+            if day.required_time.date < start_date {
+                return None;
+            }
+        }
+        self.balance += day.worked_time(key_classes) - day.required_time.required_time;
+        Some(self.balance)
+    }
+
+    pub fn balance(&self) -> chrono::Duration {
+        self.balance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_day(date: Date, worked_minutes: i64, required_minutes: i64) -> work_day::Day {
+        work_day::Day {
+            duration_of_day: chrono::Duration::hours(8),
+            required_time: required_time::RequiredTime {
+                date,
+                day_type: required_time::DayType::WorkDay,
+                required_time: chrono::Duration::minutes(required_minutes),
+                line_nr: 0,
+            },
+            work_day: work_day::WorkDay {
+                date,
+                entries: vec![
+                    work_day::Entry {
+                        start_ts: Time::from_hms_opt(8, 0, 0).unwrap(),
+                        duration: chrono::Duration::minutes(worked_minutes),
+                        key: "Foo".to_string(),
+                        sub_keys: Vec::new(),
+                        alias: None,
+                        raw_data: String::new(),
+                    },
+                    work_day::Entry {
+                        start_ts: Time::from_hms_opt(8, 0, 0).unwrap()
+                            + chrono::Duration::minutes(worked_minutes),
+                        duration: chrono::Duration::minutes(30),
+                        key: "Pause".to_string(),
+                        sub_keys: Vec::new(),
+                        alias: None,
+                        raw_data: String::new(),
+                    },
+                ],
+                additional_text: String::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_book_days() {
+        let mut account = FlextimeAccount::new(None, chrono::Duration::minutes(-30));
+        let day1 = make_day(Date::from_ymd_opt(2018, 5, 9).unwrap(), 500, 480);
+        let day2 = make_day(Date::from_ymd_opt(2018, 5, 10).unwrap(), 400, 480);
+        assert_eq!(
+            account.book_day(&day1, &work_day::KeyClasses::default()),
+            Some(chrono::Duration::minutes(-10))
+        );
+        assert_eq!(
+            account.book_day(&day2, &work_day::KeyClasses::default()),
+            Some(chrono::Duration::minutes(-90))
+        );
+        assert_eq!(account.balance(), chrono::Duration::minutes(-90));
+    }
+
+    #[test]
+    fn test_days_before_start_date_are_ignored() {
+        let mut account =
+            FlextimeAccount::new(Date::from_ymd_opt(2018, 5, 10), chrono::Duration::hours(2));
+        let day1 = make_day(Date::from_ymd_opt(2018, 5, 9).unwrap(), 500, 480);
+        let day2 = make_day(Date::from_ymd_opt(2018, 5, 10).unwrap(), 500, 480);
+        assert_eq!(
+            account.book_day(&day1, &work_day::KeyClasses::default()),
+            None
+        );
+        assert_eq!(
+            account.book_day(&day2, &work_day::KeyClasses::default()),
+            Some(chrono::Duration::minutes(140))
+        );
+    }
+}
@@ -0,0 +1,471 @@
+use self::chrono::Datelike;
+use super::work_day;
+use super::*;
+
+/// A break of at least `duration` is required if more than `after` is worked on a day
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BreakRule {
+    pub after: chrono::Duration,
+    pub duration: chrono::Duration,
+}
+
+impl std::str::FromStr for BreakRule {
+    type Err = Error;
+
+    /// Parses rules of the form '6h: 30m'
+    fn from_str(s: &str) -> Result<BreakRule> {
+        match s.split_once(':') {
+            Some((after, duration)) => Ok(BreakRule {
+                after: util::parse_duration(after.trim())?,
+                duration: util::parse_duration(duration.trim())?,
+            }),
+            None => Err(Error::CommandLine(format!(
+                "Break rule '{}' did not have the form '6h: 30m'",
+                s
+            ))),
+        }
+    }
+}
+
+/// The limits of the working time. The default values are the ones of the German
+/// Arbeitszeitgesetz (ArbZG).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ComplianceConfig {
+    pub max_daily_work: chrono::Duration,
+    pub break_rules: Vec<BreakRule>,
+    pub max_work_without_break: chrono::Duration,
+    /// Shorter interruptions of the work don't count as break
+    pub min_break: chrono::Duration,
+    /// The rest between the end of the work on one day and its start on the next day
+    pub min_rest: chrono::Duration,
+    /// Whether work on sundays and holidays is a violation
+    pub check_rest_days: bool,
+}
+
+impl Default for ComplianceConfig {
+    fn default() -> ComplianceConfig {
+        ComplianceConfig {
+            max_daily_work: chrono::Duration::hours(10),
+            break_rules: vec![
+                BreakRule {
+                    after: chrono::Duration::hours(6),
+                    duration: chrono::Duration::minutes(30),
+                },
+                BreakRule {
+                    after: chrono::Duration::hours(9),
+                    duration: chrono::Duration::minutes(45),
+                },
+            ],
+            max_work_without_break: chrono::Duration::hours(6),
+            min_break: chrono::Duration::minutes(15),
+            min_rest: chrono::Duration::hours(11),
+            check_rest_days: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ViolationKind {
+    TooMuchWork {
+        worked: chrono::Duration,
+    },
+    MissingBreak {
+        worked: chrono::Duration,
+        breaks: chrono::Duration,
+        required: chrono::Duration,
+    },
+    WorkWithoutBreak {
+        start: Time,
+        duration: chrono::Duration,
+    },
+    ShortRest {
+        rest: chrono::Duration,
+    },
+    WorkOnSunday,
+    WorkOnHoliday {
+        name: String,
+    },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Violation {
+    pub date: Date,
+    pub file: String,
+    pub kind: ViolationKind,
+}
+
+fn format_duration(duration: chrono::Duration) -> util::WorkDuration {
+    util::WorkDuration {
+        duration,
+        duration_of_day: chrono::Duration::days(1),
+    }
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}: ", self.date.format("%F (%a)"), self.file)?;
+        match self.kind {
+            ViolationKind::TooMuchWork { worked } => {
+                write!(f, "Worked {}", format_duration(worked))
+            }
+            ViolationKind::MissingBreak {
+                worked,
+                breaks,
+                required,
+            } => write!(
+                f,
+                "Breaks of {} after working {}, required are {}",
+                format_duration(breaks),
+                format_duration(worked),
+                format_duration(required)
+            ),
+            ViolationKind::WorkWithoutBreak { start, duration } => write!(
+                f,
+                "Worked {} without a break from {}",
+                format_duration(duration),
+                start.format("%H:%M")
+            ),
+            ViolationKind::ShortRest { rest } => {
+                write!(
+                    f,
+                    "Rest of {} since the previous day",
+                    format_duration(rest)
+                )
+            }
+            ViolationKind::WorkOnSunday => write!(f, "Worked on a sunday"),
+            ViolationKind::WorkOnHoliday { ref name } => {
+                write!(f, "Worked on the holiday '{}'", name)
+            }
+        }
+    }
+}
+
+/// The start of the first and the end of the last work of the day
+fn work_span(
+    day: &work_day::Day,
+    key_classes: &work_day::KeyClasses,
+) -> Option<(DateTime, DateTime)> {
+    let work_entries: Vec<work_day::Entry> = day
+        .work_day
+        .entries
+        .iter()
+        .flat_map(|entry| entry.split())
+        .filter(|entry| key_classes.is_work(&entry.key) && !entry.duration.is_zero())
+        .collect();
+    let first = work_entries.first()?;
+    let last = work_entries.last()?;
+    Some((
+        day.work_day.date.and_time(first.start_ts),
+        day.work_day.date.and_time(last.start_ts) + last.duration,
+    ))
+}
+
+/// The stretches of work of a day and the interruptions between them
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WorkPattern {
+    /// The start and the duration of the work between two breaks
+    pub stretches: Vec<(Time, chrono::Duration)>,
+    /// The sum of the interruptions between the stretches
+    pub breaks: chrono::Duration,
+    /// The interruption after the last work (e.g. a running break)
+    pub trailing_interruption: chrono::Duration,
+}
+
+/// Splits the day into stretches of work. Breaks and excluded keys interrupt the work, but only
+/// interruptions of at least `min_break` count as break.
+pub fn work_pattern(
+    day: &work_day::Day,
+    key_classes: &work_day::KeyClasses,
+    min_break: chrono::Duration,
+) -> WorkPattern {
+    let mut ret = WorkPattern {
+        stretches: Vec::new(),
+        breaks: chrono::Duration::zero(),
+        trailing_interruption: chrono::Duration::zero(),
+    };
+    let mut interruption = chrono::Duration::zero();
+    for entry in day.work_day.entries.iter().flat_map(|entry| entry.split()) {
+        if !key_classes.is_work(&entry.key) {
+            interruption += entry.duration;
+            continue;
+        }
+        if entry.duration.is_zero() {
+            continue;
+        }
+        match ret.stretches.last_mut() {
+            Some((_, duration)) if interruption < min_break => *duration += entry.duration,
+            Some(_) => {
+                ret.breaks += interruption;
+                ret.stretches.push((entry.start_ts, entry.duration));
+            }
+            None => ret.stretches.push((entry.start_ts, entry.duration)),
+        }
+        interruption = chrono::Duration::zero();
+    }
+    ret.trailing_interruption = interruption;
+    ret
+}
+
+/// The break required for the work according to the rules
+pub fn required_break(break_rules: &[BreakRule], worked: chrono::Duration) -> chrono::Duration {
+    break_rules
+        .iter()
+        .filter(|rule| worked > rule.after)
+        .map(|rule| rule.duration)
+        .max()
+        .unwrap_or_else(chrono::Duration::zero)
+}
+
+fn check_day(
+    day: &work_day::Day,
+    key_classes: &work_day::KeyClasses,
+    config: &ComplianceConfig,
+) -> Vec<ViolationKind> {
+    let mut ret = Vec::new();
+    let worked = day.worked_time(key_classes);
+    if worked.is_zero() {
+        return ret;
+    }
+    if worked > config.max_daily_work {
+        ret.push(ViolationKind::TooMuchWork { worked });
+    }
+
+    let pattern = work_pattern(day, key_classes, config.min_break);
+    for &(start, duration) in &pattern.stretches {
+        if duration > config.max_work_without_break {
+            ret.push(ViolationKind::WorkWithoutBreak { start, duration });
+        }
+    }
+
+    let required = required_break(&config.break_rules, worked);
+    if pattern.breaks < required {
+        ret.push(ViolationKind::MissingBreak {
+            worked,
+            breaks: pattern.breaks,
+            required,
+        });
+    }
+
+    if !config.check_rest_days {
+        return ret;
+    }
+    if day.work_day.date.weekday() == chrono::Weekday::Sun {
+        ret.push(ViolationKind::WorkOnSunday);
+    } else if let required_time::DayType::Holiday { ref name } = day.required_time.day_type {
+        ret.push(ViolationKind::WorkOnHoliday { name: name.clone() });
+    }
+    ret
+}
+
+/// Checks the days (sorted by date) for violations of the working time law. The files are the
+/// .work-files of the days by their date.
+pub fn check_days(
+    days: &[work_day::Day],
+    files: &std::collections::BTreeMap<Date, String>,
+    key_classes: &work_day::KeyClasses,
+    config: &ComplianceConfig,
+) -> Vec<Violation> {
+    let mut ret = Vec::new();
+    let mut previous_end: Option<DateTime> = None;
+    for day in days {
+        let date = day.work_day.date;
+        let file = files.get(&date).cloned().unwrap_or_default();
+        let mut kinds = Vec::new();
+        let span = work_span(day, key_classes);
+        if let (Some(previous_end), Some((start, _))) = (previous_end, span) {
+            if previous_end.date().succ_opt() == Some(date)
+                && start - previous_end < config.min_rest
+            {
+                kinds.push(ViolationKind::ShortRest {
+                    rest: start - previous_end,
+                });
+            }
+        }
+        previous_end = span.map(|(_, end)| end);
+        kinds.append(&mut check_day(day, key_classes, config));
+        ret.extend(kinds.into_iter().map(|kind| Violation {
+            date,
+            file: file.clone(),
+            kind,
+        }));
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_day(date: Date, day_type: required_time::DayType, txt: &str) -> work_day::Day {
+        let key_classes = work_day::KeyClasses::new(vec!["Pause".to_string()], Vec::new());
+        work_day::Day {
+            duration_of_day: chrono::Duration::hours(8),
+            required_time: required_time::RequiredTime {
+                date,
+                day_type,
+                required_time: chrono::Duration::hours(8),
+                line_nr: 0,
+            },
+            work_day: work_day::WorkDay::parse(
+                &mut std::io::BufReader::new(txt.as_bytes()),
+                Some(date),
+                false,
+                false,
+                &key_classes,
+                "tst_file",
+            )
+            .unwrap(),
+        }
+    }
+
+    fn check(days: &[work_day::Day]) -> Vec<(u32, ViolationKind)> {
+        check_with_config(days, &ComplianceConfig::default())
+    }
+
+    fn check_with_config(
+        days: &[work_day::Day],
+        config: &ComplianceConfig,
+    ) -> Vec<(u32, ViolationKind)> {
+        check_days(
+            days,
+            &std::collections::BTreeMap::new(),
+            &work_day::KeyClasses::default(),
+            config,
+        )
+        .into_iter()
+        .map(|violation| (violation.date.day(), violation.kind))
+        .collect()
+    }
+
+    #[test]
+    fn test_compliant_day() {
+        // 8h of work with a break of 30 minutes split into two
+        let day = make_day(
+            Date::from_ymd_opt(2018, 5, 9).unwrap(),
+            required_time::DayType::WorkDay,
+            r"-- 2018-05-09 Mi 08:00 -- Foo
+-- 2018-05-09 Mi 11:00 -- Pause
+-- 2018-05-09 Mi 11:15 -- Bar
+-- 2018-05-09 Mi 14:00 -- Pause
+-- 2018-05-09 Mi 14:15 -- Baz
+-- 2018-05-09 Mi 16:30 -- Pause",
+        );
+        assert_eq!(check(&[day]), Vec::new());
+    }
+
+    #[test]
+    fn test_long_day_without_breaks() {
+        // 10h 40m of work with interruptions of 10 minutes only
+        let day = make_day(
+            Date::from_ymd_opt(2018, 5, 9).unwrap(),
+            required_time::DayType::WorkDay,
+            r"-- 2018-05-09 Mi 07:00 -- Foo
+-- 2018-05-09 Mi 12:00 -- Pause
+-- 2018-05-09 Mi 12:10 -- Bar
+-- 2018-05-09 Mi 17:50 -- Pause",
+        );
+        assert_eq!(
+            check(&[day]),
+            vec![
+                (
+                    9,
+                    ViolationKind::TooMuchWork {
+                        worked: chrono::Duration::minutes(640)
+                    }
+                ),
+                (
+                    9,
+                    ViolationKind::WorkWithoutBreak {
+                        start: Time::from_hms_opt(7, 0, 0).unwrap(),
+                        duration: chrono::Duration::minutes(640)
+                    }
+                ),
+                (
+                    9,
+                    ViolationKind::MissingBreak {
+                        worked: chrono::Duration::minutes(640),
+                        breaks: chrono::Duration::zero(),
+                        required: chrono::Duration::minutes(45)
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_short_rest_and_work_on_sundays_and_holidays() {
+        let saturday = make_day(
+            Date::from_ymd_opt(2018, 5, 12).unwrap(),
+            required_time::DayType::WeekEnd,
+            r"-- 2018-05-12 Sa 18:00 -- Foo
+-- 2018-05-12 Sa 22:00 -- Pause",
+        );
+        let sunday = make_day(
+            Date::from_ymd_opt(2018, 5, 13).unwrap(),
+            required_time::DayType::WeekEnd,
+            r"-- 2018-05-13 So 07:00 -- Foo
+-- 2018-05-13 So 08:00 -- Pause",
+        );
+        let holiday = make_day(
+            Date::from_ymd_opt(2018, 5, 21).unwrap(),
+            required_time::DayType::Holiday {
+                name: "Pfingstmontag".to_string(),
+            },
+            r"-- 2018-05-21 Mo 07:00 -- Foo
+-- 2018-05-21 Mo 08:00 -- Pause",
+        );
+        assert_eq!(
+            check(&[saturday, sunday, holiday]),
+            vec![
+                (
+                    13,
+                    ViolationKind::ShortRest {
+                        rest: chrono::Duration::hours(9)
+                    }
+                ),
+                (13, ViolationKind::WorkOnSunday),
+                (
+                    21,
+                    ViolationKind::WorkOnHoliday {
+                        name: "Pfingstmontag".to_string()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_work_on_sundays_and_holidays_without_rest_day_checks() {
+        let sunday = make_day(
+            Date::from_ymd_opt(2018, 5, 13).unwrap(),
+            required_time::DayType::WeekEnd,
+            r"-- 2018-05-13 So 07:00 -- Foo
+-- 2018-05-13 So 08:00 -- Pause",
+        );
+        let holiday = make_day(
+            Date::from_ymd_opt(2018, 5, 21).unwrap(),
+            required_time::DayType::Holiday {
+                name: "Pfingstmontag".to_string(),
+            },
+            r"-- 2018-05-21 Mo 07:00 -- Foo
+-- 2018-05-21 Mo 08:00 -- Pause",
+        );
+        let config = ComplianceConfig {
+            check_rest_days: false,
+            ..ComplianceConfig::default()
+        };
+        assert_eq!(check_with_config(&[sunday, holiday], &config), Vec::new());
+    }
+
+    #[test]
+    fn test_parse_break_rule() {
+        assert_eq!(
+            "6h: 30m".parse::<BreakRule>(),
+            Ok(BreakRule {
+                after: chrono::Duration::hours(6),
+                duration: chrono::Duration::minutes(30),
+            })
+        );
+        assert!("6h 30m".parse::<BreakRule>().is_err());
+    }
+}
@@ -0,0 +1,375 @@
+use super::*;
+
+/// A password or token. It is never written by `Debug`, such that it does not leak into the
+/// debugging output.
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Secret(<redacted>)")
+    }
+}
+
+impl std::str::FromStr for Secret {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Secret> {
+        Ok(Secret(s.to_string()))
+    }
+}
+
+/// The credentials of one machine of a `.netrc` file
+#[derive(Debug, Eq, PartialEq)]
+pub struct NetrcEntry {
+    pub login: Option<String>,
+    pub password: Option<Secret>,
+}
+
+/// Returns the entry of the `.netrc` file for the given host. If there is no entry for the host
+/// the `default` entry is returned.
+pub fn parse_netrc(stream: &mut dyn std::io::BufRead, host: &str) -> Result<Option<NetrcEntry>> {
+    let mut content = String::new();
+    stream.read_to_string(&mut content)?;
+    let mut tokens = content.split_whitespace();
+    let mut found: Option<NetrcEntry> = None;
+    let mut default: Option<NetrcEntry> = None;
+    // which entry the current tokens belong to: the one for the host, the default or another one
+    enum Current {
+        Host,
+        Default,
+        Other,
+    }
+    let mut current = Current::Other;
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" => {
+                current = match tokens.next() {
+                    Some(machine) if machine == host && found.is_none() => {
+                        found = Some(NetrcEntry {
+                            login: None,
+                            password: None,
+                        });
+                        Current::Host
+                    }
+                    _ => Current::Other,
+                }
+            }
+            "default" => {
+                default = Some(NetrcEntry {
+                    login: None,
+                    password: None,
+                });
+                current = Current::Default;
+            }
+            "login" | "password" | "account" => {
+                let value = tokens.next().map(|value| value.to_string());
+                let entry = match current {
+                    Current::Host => found.as_mut(),
+                    Current::Default => default.as_mut(),
+                    Current::Other => None,
+                };
+                if let Some(entry) = entry {
+                    match token {
+                        "login" => entry.login = value,
+                        "password" => entry.password = value.map(Secret),
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(found.or(default))
+}
+
+/// Looks up the credentials for the host of the given URL in `~/.netrc`
+pub fn lookup_netrc(base_url: &str) -> Result<Option<NetrcEntry>> {
+    let host = reqwest::Url::parse(base_url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_string()));
+    let host = match host {
+        Some(host) => host,
+        None => {
+            return Err(Error::Credentials(format!(
+                "Can't determine the host of '{}'",
+                base_url
+            )))
+        }
+    };
+    let netrc_file = match directories::BaseDirs::new() {
+        Some(base_dirs) => base_dirs.home_dir().join(".netrc"),
+        None => return Ok(None),
+    };
+    match std::fs::File::open(netrc_file) {
+        Ok(file) => parse_netrc(&mut std::io::BufReader::new(file), &host),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Runs the command (e.g. `pass show jira`) in a shell and returns the first line of its output
+pub fn run_secret_command(command: &str) -> Result<Secret> {
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stderr(std::process::Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::Credentials(format!(
+            "The command '{}' failed with {}",
+            command, output.status
+        )));
+    }
+    let stdout = String::from_utf8(output.stdout).map_err(|_| {
+        Error::Credentials(format!("The command '{}' returned no valid UTF-8", command))
+    })?;
+    match stdout.lines().next() {
+        Some(line) if !line.is_empty() => Ok(Secret(line.to_string())),
+        _ => Err(Error::Credentials(format!(
+            "The command '{}' did not return anything",
+            command
+        ))),
+    }
+}
+
+/// Determines the JIRA username and the authentication. A token is preferred over a password. The
+/// password is taken from the command line, the rc-file or the environment, then from the output
+/// of the password command and finally from the `.netrc` entry returned by `lookup_netrc`, whose
+/// login is used if no username is given. An entry with the login of another user is ignored.
+pub fn jira_authentication(
+    username: Option<String>,
+    token: Option<&Secret>,
+    password: Option<&Secret>,
+    password_command: Option<&str>,
+    lookup_netrc: &dyn Fn() -> Result<Option<NetrcEntry>>,
+) -> Result<(String, jira::Authentication)> {
+    let missing_username = || Error::Credentials("Missing JIRA username".to_string());
+    if let Some(token) = token {
+        return Ok((
+            username.ok_or_else(missing_username)?,
+            jira::Authentication::Bearer {
+                token: token.expose().to_string(),
+            },
+        ));
+    }
+    let mut username = username;
+    let password = match (password, password_command) {
+        (Some(password), _) => Some(password.clone()),
+        (None, Some(command)) => Some(run_secret_command(command)?),
+        (None, None) => match lookup_netrc()? {
+            // the entry of another user is of no use
+            Some(entry)
+                if username.is_some() && entry.login.is_some() && username != entry.login =>
+            {
+                None
+            }
+            Some(entry) => {
+                username = username.or(entry.login);
+                entry.password
+            }
+            None => None,
+        },
+    };
+    let username = username.ok_or_else(missing_username)?;
+    let password =
+        password.ok_or_else(|| Error::Credentials("Missing JIRA password or token".to_string()))?;
+    Ok((
+        username.clone(),
+        jira::Authentication::Basic {
+            username,
+            password: password.expose().to_string(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn test_secret_is_redacted() {
+        let secret = Secret("top secret".to_string());
+        assert_eq!(format!("{:?}", Some(secret)), "Some(Secret(<redacted>))");
+    }
+
+    #[test]
+    fn test_parse_netrc() {
+        let txt: &str = r"machine other.example.com login foo password bar
+machine jira.example.com
+    login me
+    password s3cret
+default login anonymous password guest";
+        let mut txt = io::BufReader::new(txt.as_bytes());
+        let entry = parse_netrc(&mut txt, "jira.example.com");
+        assert_eq!(
+            entry,
+            Ok(Some(NetrcEntry {
+                login: Some("me".to_string()),
+                password: Some(Secret("s3cret".to_string())),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_netrc_default() {
+        let txt: &str = r"machine other.example.com login foo password bar
+default login anonymous password guest";
+        let mut txt = io::BufReader::new(txt.as_bytes());
+        let entry = parse_netrc(&mut txt, "jira.example.com");
+        assert_eq!(
+            entry,
+            Ok(Some(NetrcEntry {
+                login: Some("anonymous".to_string()),
+                password: Some(Secret("guest".to_string())),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_netrc_without_match() {
+        let txt: &str = r"machine other.example.com login foo password bar";
+        let mut txt = io::BufReader::new(txt.as_bytes());
+        assert_eq!(parse_netrc(&mut txt, "jira.example.com"), Ok(None));
+    }
+
+    fn secret(s: &str) -> Secret {
+        Secret(s.to_string())
+    }
+
+    fn no_netrc() -> Result<Option<NetrcEntry>> {
+        panic!(".netrc must not be looked up")
+    }
+
+    fn netrc() -> Result<Option<NetrcEntry>> {
+        Ok(Some(NetrcEntry {
+            login: Some("netrc_user".to_string()),
+            password: Some(secret("from_netrc")),
+        }))
+    }
+
+    /// The username and the kind of authentication with its secret
+    fn describe(result: Result<(String, jira::Authentication)>) -> Result<(String, String)> {
+        result.map(|(username, authentication)| match authentication {
+            jira::Authentication::Basic {
+                username: _,
+                password,
+            } => (username, format!("Basic {}", password)),
+            jira::Authentication::Bearer { token } => (username, format!("Bearer {}", token)),
+        })
+    }
+
+    #[test]
+    fn test_jira_authentication_token_first() {
+        assert_eq!(
+            describe(jira_authentication(
+                Some("me".to_string()),
+                Some(&secret("token")),
+                Some(&secret("password")),
+                Some("echo from_command"),
+                &no_netrc,
+            )),
+            Ok(("me".to_string(), "Bearer token".to_string()))
+        );
+        assert_eq!(
+            describe(jira_authentication(
+                None,
+                Some(&secret("token")),
+                None,
+                None,
+                &no_netrc
+            )),
+            Err(Error::Credentials("Missing JIRA username".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_jira_authentication_password_before_command() {
+        assert_eq!(
+            describe(jira_authentication(
+                Some("me".to_string()),
+                None,
+                Some(&secret("password")),
+                Some("echo from_command"),
+                &no_netrc,
+            )),
+            Ok(("me".to_string(), "Basic password".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_jira_authentication_command_before_netrc() {
+        assert_eq!(
+            describe(jira_authentication(
+                Some("me".to_string()),
+                None,
+                None,
+                Some("echo from_command"),
+                &no_netrc,
+            )),
+            Ok(("me".to_string(), "Basic from_command".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_jira_authentication_netrc() {
+        assert_eq!(
+            describe(jira_authentication(None, None, None, None, &netrc)),
+            Ok(("netrc_user".to_string(), "Basic from_netrc".to_string()))
+        );
+        assert_eq!(
+            describe(jira_authentication(
+                Some("netrc_user".to_string()),
+                None,
+                None,
+                None,
+                &netrc
+            )),
+            Ok(("netrc_user".to_string(), "Basic from_netrc".to_string()))
+        );
+        assert_eq!(
+            describe(jira_authentication(
+                Some("me".to_string()),
+                None,
+                None,
+                None,
+                &|| Ok(Some(NetrcEntry {
+                    login: None,
+                    password: Some(secret("from_netrc")),
+                }))
+            )),
+            Ok(("me".to_string(), "Basic from_netrc".to_string()))
+        );
+        // the password of another user is not used
+        assert_eq!(
+            describe(jira_authentication(
+                Some("me".to_string()),
+                None,
+                None,
+                None,
+                &netrc
+            )),
+            Err(Error::Credentials(
+                "Missing JIRA password or token".to_string()
+            ))
+        );
+        assert_eq!(
+            describe(jira_authentication(
+                Some("me".to_string()),
+                None,
+                None,
+                None,
+                &|| Ok(None)
+            )),
+            Err(Error::Credentials(
+                "Missing JIRA password or token".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use super::work_day;
+use super::*;
+
+/// The settings of the CSV export. They allow the export to be read by spreadsheet tools with
+/// differing locales (e.g. German ones expect ';' as delimiter and ',' as decimal separator).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CsvConfig {
+    pub delimiter: char,
+    pub decimal_separator: char,
+}
+
+impl Default for CsvConfig {
+    fn default() -> CsvConfig {
+        CsvConfig {
+            delimiter: ',',
+            decimal_separator: '.',
+        }
+    }
+}
+
+impl CsvConfig {
+    fn quote(&self, field: &str) -> String {
+        if field.contains(self.delimiter)
+            || field.contains('"')
+            || field.contains('\n')
+            || field.contains('\r')
+        {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    fn write_row(&self, out: &mut dyn std::io::Write, fields: &[String]) -> std::io::Result<()> {
+        let fields: Vec<_> = fields.iter().map(|field| self.quote(field)).collect();
+        writeln!(
+            out,
+            "{}",
+            itertools::join(&fields, &self.delimiter.to_string())
+        )
+    }
+
+    fn format_hours(&self, duration: &chrono::Duration) -> String {
+        format!("{:.2}", duration.num_minutes() as f64 / 60.)
+            .replace('.', &self.decimal_separator.to_string())
+    }
+}
+
+/// Writes one row per entry of the given days preceded by a header row. Entries naming several
+/// tickets are split into one row per ticket (see `Entry::split`).
+pub fn write_entries(
+    out: &mut dyn std::io::Write,
+    days: &[work_day::Day],
+    config: &CsvConfig,
+) -> std::io::Result<()> {
+    config.write_row(
+        out,
+        &[
+            "date",
+            "weekday",
+            "start",
+            "end",
+            "duration_minutes",
+            "duration_hours",
+            "key",
+            "sub_keys",
+            "day_type",
+        ]
+        .map(|header| header.to_string()),
+    )?;
+    for day in days {
+        for entry in day.work_day.entries.iter().flat_map(|entry| entry.split()) {
+            let end_ts = entry.start_ts + entry.duration;
+            config.write_row(
+                out,
+                &[
+                    day.work_day.date.format("%F").to_string(),
+                    day.work_day.date.format("%a").to_string(),
+                    entry.start_ts.format("%H:%M").to_string(),
+                    end_ts.format("%H:%M").to_string(),
+                    entry.duration.num_minutes().to_string(),
+                    config.format_hours(&entry.duration),
+                    entry.key.clone(),
+                    itertools::join(&entry.sub_keys, " "),
+                    day.required_time.day_type.to_string(),
+                ],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_entries_german_locale() {
+        let date = Date::from_ymd_opt(2018, 5, 9).unwrap();
+        let days = [work_day::Day {
+            duration_of_day: chrono::Duration::hours(8),
+            required_time: required_time::RequiredTime {
+                date,
+                day_type: required_time::DayType::WorkDay,
+                required_time: chrono::Duration::hours(8),
+                line_nr: 0,
+            },
+            work_day: work_day::WorkDay {
+                date,
+                entries: vec![
+                    work_day::Entry {
+                        start_ts: Time::from_hms_opt(7, 25, 0).unwrap(),
+                        duration: chrono::Duration::minutes(169),
+                        key: "Foo".to_string(),
+                        sub_keys: vec!["a;b".to_string(), "c".to_string()],
+                        alias: None,
+                        raw_data: String::new(),
+                    },
+                    work_day::Entry {
+                        start_ts: Time::from_hms_opt(10, 14, 0).unwrap(),
+                        duration: chrono::Duration::minutes(0),
+                        key: "Pause".to_string(),
+                        sub_keys: Vec::new(),
+                        alias: None,
+                        raw_data: String::new(),
+                    },
+                ],
+                additional_text: String::new(),
+            },
+        }];
+        let mut out = Vec::new();
+        write_entries(
+            &mut out,
+            &days,
+            &CsvConfig {
+                delimiter: ';',
+                decimal_separator: ',',
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "date;weekday;start;end;duration_minutes;duration_hours;key;sub_keys;day_type
+2018-05-09;Wed;07:25;10:14;169;2,82;Foo;\"a;b c\";WorkDay
+2018-05-09;Wed;10:14;10:14;0;0,00;Pause;;WorkDay
+"
+        );
+    }
+
+    #[test]
+    fn test_write_entries_with_several_tickets() {
+        let date = Date::from_ymd_opt(2018, 5, 9).unwrap();
+        let days = [work_day::Day {
+            duration_of_day: chrono::Duration::hours(8),
+            required_time: required_time::RequiredTime {
+                date,
+                day_type: required_time::DayType::WorkDay,
+                required_time: chrono::Duration::hours(8),
+                line_nr: 0,
+            },
+            work_day: work_day::WorkDay {
+                date,
+                entries: vec![
+                    work_day::Entry {
+                        start_ts: Time::from_hms_opt(8, 0, 0).unwrap(),
+                        duration: chrono::Duration::minutes(60),
+                        key: "PROJ-1|PROJ-2".to_string(),
+                        sub_keys: Vec::new(),
+                        alias: None,
+                        raw_data: String::new(),
+                    },
+                    work_day::Entry {
+                        start_ts: Time::from_hms_opt(9, 0, 0).unwrap(),
+                        duration: chrono::Duration::minutes(0),
+                        key: "Pause".to_string(),
+                        sub_keys: Vec::new(),
+                        alias: None,
+                        raw_data: String::new(),
+                    },
+                ],
+                additional_text: String::new(),
+            },
+        }];
+        let mut out = Vec::new();
+        write_entries(&mut out, &days, &CsvConfig::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "date,weekday,start,end,duration_minutes,duration_hours,key,sub_keys,day_type
+2018-05-09,Wed,08:00,08:30,30,0.50,PROJ-1,,WorkDay
+2018-05-09,Wed,08:30,09:00,30,0.50,PROJ-2,,WorkDay
+2018-05-09,Wed,09:00,09:00,0,0.00,Pause,,WorkDay
+"
+        );
+    }
+}
@@ -0,0 +1,806 @@
+extern crate ical;
+
+use super::required_time::{DayType, DayTypeEntry};
+use super::*;
+
+/// The way a rule recognizes the events it applies to
+#[derive(Clone, Debug)]
+enum Pattern {
+    Text(String),
+    /// Has to match the whole category or summary
+    Regex(regex::Regex),
+}
+
+impl Pattern {
+    fn matches(&self, s: &str) -> bool {
+        match *self {
+            Pattern::Text(ref text) => text == s,
+            Pattern::Regex(ref re) => re.is_match(s),
+        }
+    }
+}
+
+/// Maps events of a calendar to a day type of the holidays file, e.g. 'Urlaub -> U' or
+/// '/.*[Kk]rank.*/ -> K'. The pattern is matched against the categories and the summary of the
+/// events.
+#[derive(Clone, Debug)]
+pub struct IcsRule {
+    pattern: Pattern,
+    letter: String,
+}
+
+impl std::str::FromStr for IcsRule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<IcsRule> {
+        lazy_static::lazy_static! {
+            static ref RE: regex::Regex = regex::Regex::new(r"^(/(.+)/|(.+?)) *-> *([WKFUHÜ])$")
+                .expect("Erronuous Regular Expression for calendar rule parsing");
+        }
+        let error = || {
+            Error::CommandLine(format!(
+                "Calendar rule '{}' did not have the form 'Category -> U' or '/regex/ -> U'",
+                s
+            ))
+        };
+        let c = RE.captures(s.trim()).ok_or_else(error)?;
+        let pattern = match (c.get(2), c.get(3)) {
+            (Some(re), _) => Pattern::Regex(
+                regex::Regex::new(&format!("^(?:{})$", re.as_str())).map_err(|_| error())?,
+            ),
+            (None, Some(text)) => Pattern::Text(text.as_str().to_string()),
+            (None, None) => return Err(error()),
+        };
+        Ok(IcsRule {
+            pattern,
+            letter: c[4].to_string(),
+        })
+    }
+}
+
+fn property_value<'a>(
+    event: &'a ical::parser::ical::component::IcalEvent,
+    name: &str,
+) -> Option<&'a str> {
+    event
+        .properties
+        .iter()
+        .find(|property| property.name == name)
+        .and_then(|property| property.value.as_deref())
+}
+
+/// Removes the escaping of TEXT values (RFC 5545, 3.3.11)
+fn unescape(s: &str) -> String {
+    let mut ret = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => ret.push(' '),
+            Some(c) => ret.push(c),
+            None => ret.push('\\'),
+        }
+    }
+    ret
+}
+
+/// Splits a CATEGORIES value at the commas which are not escaped
+fn split_categories(s: &str) -> Vec<String> {
+    let mut ret = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            ',' if !escaped => {
+                ret.push(unescape(&s[start..i]).trim().to_string());
+                start = i + 1;
+            }
+            _ => escaped = false,
+        }
+    }
+    ret.push(unescape(&s[start..]).trim().to_string());
+    ret
+}
+
+/// Parses a DATE or DATE-TIME value. Times in UTC ('...Z') are converted to the timezone, other
+/// times are taken as they are. The flag tells whether the value is a DATE.
+fn parse_date_time(value: &str, timezone: &jira::TimeZone) -> Option<(DateTime, bool)> {
+    lazy_static::lazy_static! {
+        static ref RE: regex::Regex =
+            regex::Regex::new(r"^(\d{4})(\d{2})(\d{2})(T(\d{2})(\d{2})(\d{2})(Z)?)?$")
+                .expect("Erronuous Regular Expression for calendar date parsing");
+    }
+    let c = RE.captures(value.trim())?;
+    let date = util::to_date(&c[1], &c[2], &c[3]).ok()?;
+    if c.get(4).is_none() {
+        return Some((date.and_time(Time::MIN), true));
+    }
+    let time = Time::from_hms_opt(c[5].parse().ok()?, c[6].parse().ok()?, c[7].parse().ok()?)?;
+    let date_time = date.and_time(time);
+    if c.get(8).is_some() {
+        Some((timezone.utc_to_local(&date_time), false))
+    } else {
+        Some((date_time, false))
+    }
+}
+
+/// Parses a DURATION value of the form 'P1W' or 'P1DT2H30M' (RFC 5545, 3.3.6). Negative durations
+/// are not accepted.
+fn parse_duration(value: &str) -> Option<chrono::Duration> {
+    lazy_static::lazy_static! {
+        static ref RE: regex::Regex = regex::Regex::new(
+            r"^\+?P(?:(\d+)W|(?:(\d+)D)?(?:T(?:(\d+)H)?(?:(\d+)M)?(?:(\d+)S)?)?)$"
+        )
+        .expect("Erronuous Regular Expression for calendar duration parsing");
+    }
+    let c = RE.captures(value.trim())?;
+    let number = |i: usize| c.get(i).map_or(Some(0), |n| n.as_str().parse::<i64>().ok());
+    Some(
+        chrono::Duration::weeks(number(1)?)
+            + chrono::Duration::days(number(2)?)
+            + chrono::Duration::hours(number(3)?)
+            + chrono::Duration::minutes(number(4)?)
+            + chrono::Duration::seconds(number(5)?),
+    )
+}
+
+/// Parses the all-day events (or the days touched by other events) of an iCalendar stream. The
+/// first rule matching a category or else the summary of an event determines its day type, events
+/// without a matching rule are ignored. The summary serves as description and the number of the
+/// event as line_nr. Times in UTC are converted to the timezone. Recurring events are rejected.
+pub fn parse_ics(
+    stream: &mut dyn std::io::BufRead,
+    file_name: &str,
+    rules: &[IcsRule],
+    timezone: &jira::TimeZone,
+) -> Result<Vec<DayTypeEntry>> {
+    let error = |message: String| Error::ParseCalendar {
+        file: file_name.to_string(),
+        message,
+    };
+    let mut ret = Vec::new();
+    let mut event_nr = 0u32;
+    for calendar in ical::IcalParser::new(stream) {
+        let calendar = calendar.map_err(|e| error(e.to_string()))?;
+        for event in &calendar.events {
+            event_nr += 1;
+            let summary = property_value(event, "SUMMARY").map_or_else(String::new, unescape);
+            let categories: Vec<String> = property_value(event, "CATEGORIES")
+                .map(split_categories)
+                .unwrap_or_default();
+            let rule = rules.iter().find(|rule| {
+                categories
+                    .iter()
+                    .any(|category| rule.pattern.matches(category))
+                    || rule.pattern.matches(&summary)
+            });
+            let rule = match rule {
+                Some(rule) => rule,
+                None => continue,
+            };
+            if property_value(event, "RRULE").is_some() || property_value(event, "RDATE").is_some()
+            {
+                return Err(error(format!(
+                    "Event {} is recurring, which is not supported",
+                    event_nr
+                )));
+            }
+            let (start, start_is_date) = property_value(event, "DTSTART")
+                .and_then(|value| parse_date_time(value, timezone))
+                .ok_or_else(|| error(format!("Event {} has no valid DTSTART", event_nr)))?;
+            let end = match (
+                property_value(event, "DTEND"),
+                property_value(event, "DURATION"),
+            ) {
+                (Some(value), _) => parse_date_time(value, timezone)
+                    .map(|(end, _)| end)
+                    .filter(|end| *end >= start)
+                    .ok_or_else(|| error(format!("Event {} has an invalid DTEND", event_nr)))?,
+                (None, Some(value)) => parse_duration(value)
+                    .map(|duration| start + duration)
+                    .ok_or_else(|| error(format!("Event {} has an invalid DURATION", event_nr)))?,
+                // an all-day event without end lasts one day
+                (None, None) if start_is_date => start + chrono::Duration::days(1),
+                (None, None) => start,
+            };
+            let start_date = start.date();
+            // the end is exclusive if it lies at the start of a day
+            let end_date = if end > start && end.time() == Time::MIN {
+                end.date().pred_opt().ok_or(Error::ParseDay)?
+            } else {
+                end.date()
+            };
+            let day_type = DayType::from_letter(&rule.letter, summary.clone())
+                .expect("Calendar rule with invalid day type");
+            let mut curr_day = start_date;
+            while curr_day <= end_date {
+                ret.push(DayTypeEntry::new(
+                    curr_day,
+                    day_type.clone(),
+                    start_date != end_date,
+                    file_name,
+                    event_nr,
+                ));
+                curr_day = curr_day.succ_opt().ok_or(Error::ParseDay)?;
+            }
+        }
+    }
+    Ok(ret)
+}
+
+pub fn parse_ics_file(
+    file_name: &std::path::PathBuf,
+    rules: &[IcsRule],
+    timezone: &jira::TimeZone,
+) -> Result<Vec<DayTypeEntry>> {
+    let file = std::fs::File::open(file_name)?;
+    let mut fstream = std::io::BufReader::new(file);
+    let file_name_str = match file_name.to_str() {
+        Some(fi) => fi,
+        None => {
+            return Err(Error::InvalidFileName {
+                file: file_name.clone(),
+            })
+        }
+    };
+    parse_ics(&mut fstream, file_name_str, rules, timezone)
+}
+
+/// How much of an absence is revealed by the summary of an exported event
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Privacy {
+    /// The day type and the description, e.g. 'Vacation: Ostsee'
+    Full,
+    /// Only the day type, e.g. 'Vacation'
+    Type,
+    /// Only 'Absent'
+    Busy,
+}
+
+impl std::str::FromStr for Privacy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Privacy> {
+        match s {
+            "full" => Ok(Privacy::Full),
+            "type" => Ok(Privacy::Type),
+            "busy" => Ok(Privacy::Busy),
+            _ => Err(Error::CommandLine(format!(
+                "Unknown privacy '{}', expected one of 'full', 'type' or 'busy'",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IcsExportConfig {
+    pub privacy: Privacy,
+    /// Put in front of every summary, e.g. the name of the person for a team calendar
+    pub summary_prefix: String,
+}
+
+/// An all-day event spanning the days from first_date to last_date (both inclusive)
+struct AbsenceEvent<'a> {
+    first_date: Date,
+    last_date: Date,
+    entry: &'a DayTypeEntry,
+}
+
+/// Merges the consecutive days of each range of the holidays file into one event
+fn merge_ranges(entries: &[DayTypeEntry]) -> Vec<AbsenceEvent<'_>> {
+    let mut ret: Vec<AbsenceEvent> = Vec::new();
+    for entry in entries {
+        if let Some(event) = ret.last_mut() {
+            if entry.given_as_range()
+                && event.entry.file() == entry.file()
+                && event.entry.line_nr() == entry.line_nr()
+                && event.entry.day_type() == entry.day_type()
+                && event.last_date.succ_opt() == Some(entry.date())
+            {
+                event.last_date = entry.date();
+                continue;
+            }
+        }
+        ret.push(AbsenceEvent {
+            first_date: entry.date(),
+            last_date: entry.date(),
+            entry,
+        });
+    }
+    ret
+}
+
+/// Escapes TEXT values (RFC 5545, 3.3.11)
+fn escape(s: &str) -> String {
+    let mut ret = String::new();
+    for c in s.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                ret.push('\\');
+                ret.push(c);
+            }
+            '\n' => ret.push_str("\\n"),
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// Writes a content line folded after 75 octets (RFC 5545, 3.1)
+fn write_line(writer: &mut dyn std::io::Write, line: &str) -> Result<()> {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > 75 {
+            writer.write_all(b"\r\n ")?;
+            octets = 1;
+        }
+        write!(writer, "{}", c)?;
+        octets += c.len_utf8();
+    }
+    writer.write_all(b"\r\n")?;
+    Ok(())
+}
+
+/// Writes the entries of the holidays file as all-day events. The days of a range become one
+/// event. The timestamp is used as DTSTAMP of the events.
+pub fn write_ics(
+    writer: &mut dyn std::io::Write,
+    entries: &[DayTypeEntry],
+    config: &IcsExportConfig,
+    timestamp: &DateTime,
+) -> Result<()> {
+    write_line(writer, "BEGIN:VCALENDAR")?;
+    write_line(writer, "VERSION:2.0")?;
+    write_line(writer, "PRODID:-//belgoking//log_work//EN")?;
+    for event in merge_ranges(entries) {
+        let day_type = event.entry.day_type();
+        let summary = match (config.privacy, day_type.description()) {
+            (Privacy::Full, Some(description)) if !description.is_empty() => {
+                format!("{}: {}", day_type.name(), description)
+            }
+            (Privacy::Full, _) | (Privacy::Type, _) => day_type.name().to_string(),
+            (Privacy::Busy, _) => "Absent".to_string(),
+        };
+        write_line(writer, "BEGIN:VEVENT")?;
+        write_line(
+            writer,
+            &format!(
+                "UID:{}-{}@log_work",
+                event.first_date.format("%Y%m%d"),
+                event.entry.line_nr()
+            ),
+        )?;
+        write_line(
+            writer,
+            &format!("DTSTAMP:{}", timestamp.format("%Y%m%dT%H%M%SZ")),
+        )?;
+        write_line(
+            writer,
+            &format!("DTSTART;VALUE=DATE:{}", event.first_date.format("%Y%m%d")),
+        )?;
+        // DTEND of all-day events is exclusive
+        let end_date = event.last_date.succ_opt().ok_or(Error::ParseDay)?;
+        write_line(
+            writer,
+            &format!("DTEND;VALUE=DATE:{}", end_date.format("%Y%m%d")),
+        )?;
+        write_line(
+            writer,
+            &format!(
+                "SUMMARY:{}",
+                escape(&format!("{}{}", config.summary_prefix, summary))
+            ),
+        )?;
+        if config.privacy != Privacy::Busy {
+            write_line(writer, &format!("CATEGORIES:{}", day_type.name()))?;
+        }
+        write_line(writer, "TRANSP:OPAQUE")?;
+        write_line(writer, "END:VEVENT")?;
+    }
+    write_line(writer, "END:VCALENDAR")?;
+    Ok(())
+}
+
+pub fn write_ics_file(
+    file_name: &std::path::PathBuf,
+    entries: &[DayTypeEntry],
+    config: &IcsExportConfig,
+    timestamp: &DateTime,
+) -> Result<()> {
+    let mut file = std::io::BufWriter::new(std::fs::File::create(file_name)?);
+    write_ics(&mut file, entries, config, timestamp)?;
+    std::io::Write::flush(&mut file)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//Example//Holidays//DE\r
+BEGIN:VEVENT\r
+UID:1@example.com\r
+DTSTART;VALUE=DATE:20180510\r
+DTEND;VALUE=DATE:20180511\r
+SUMMARY:Christi Himmelfahrt\r
+CATEGORIES:Feiertag\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:2@example.com\r
+DTSTART;VALUE=DATE:20180507\r
+DTEND;VALUE=DATE:20180512\r
+SUMMARY:Urlaub Ostsee\\, genehmigt\r
+CATEGORIES:Abwesenheit\\, lang,Urlaub\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:3@example.com\r
+DTSTART:20180514T090000Z\r
+DTEND:20180514T170000Z\r
+SUMMARY:Arzttermin krank\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:4@example.com\r
+DTSTART:20180515T090000Z\r
+SUMMARY:Team Meeting\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    fn rules() -> Vec<IcsRule> {
+        ["Feiertag -> F", "Urlaub -> U", "/.*krank.*/ -> K"]
+            .iter()
+            .map(|rule| rule.parse().unwrap())
+            .collect()
+    }
+
+    fn berlin() -> jira::TimeZone {
+        jira::TimeZone::Tz(chrono_tz::Europe::Berlin)
+    }
+
+    fn parse_events(events: &str) -> Result<Vec<DayTypeEntry>> {
+        parse_ics(
+            &mut std::io::BufReader::new(
+                format!("BEGIN:VCALENDAR\r\n{}END:VCALENDAR\r\n", events).as_bytes(),
+            ),
+            "tst_file",
+            &rules(),
+            &berlin(),
+        )
+    }
+
+    #[test]
+    fn test_parse_ics() {
+        let entries = parse_ics(
+            &mut std::io::BufReader::new(CALENDAR.as_bytes()),
+            "tst_file",
+            &rules(),
+            &berlin(),
+        )
+        .unwrap();
+        let date = |day| Date::from_ymd_opt(2018, 5, day).unwrap();
+        let vacation = DayType::Vacation {
+            description: "Urlaub Ostsee, genehmigt".to_string(),
+        };
+        let mut expected = vec![DayTypeEntry::new(
+            date(10),
+            DayType::Holiday {
+                name: "Christi Himmelfahrt".to_string(),
+            },
+            false,
+            "tst_file",
+            1,
+        )];
+        for day in 7..=11 {
+            expected.push(DayTypeEntry::new(
+                date(day),
+                vacation.clone(),
+                true,
+                "tst_file",
+                2,
+            ));
+        }
+        expected.push(DayTypeEntry::new(
+            date(14),
+            DayType::Sick {
+                description: "Arzttermin krank".to_string(),
+            },
+            false,
+            "tst_file",
+            3,
+        ));
+        assert_eq!(entries, expected);
+
+        // the holiday takes precedence over the vacation given as range
+        let required_times = required_time::consolidate_required_time(
+            &entries,
+            &date(7),
+            &date(11),
+            &schedule::Schedule::from_duration_of_day(chrono::Duration::hours(8)).into(),
+        )
+        .unwrap();
+        assert_eq!(
+            required_times[3].day_type,
+            DayType::Holiday {
+                name: "Christi Himmelfahrt".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_conflict_with_holidays_file() {
+        let holidays = required_time::parse_required_time(
+            &mut std::io::BufReader::new("2018-05-14 -- U Urlaub".as_bytes()),
+            "holidays",
+        )
+        .unwrap();
+        let events = parse_ics(
+            &mut std::io::BufReader::new(CALENDAR.as_bytes()),
+            "calendar.ics",
+            &rules(),
+            &berlin(),
+        )
+        .unwrap();
+        let consolidate = |entries: Vec<DayTypeEntry>| {
+            required_time::consolidate_required_time(
+                &entries,
+                &Date::from_ymd_opt(2018, 5, 14).unwrap(),
+                &Date::from_ymd_opt(2018, 5, 14).unwrap(),
+                &schedule::Schedule::from_duration_of_day(chrono::Duration::hours(8)).into(),
+            )
+        };
+        // the first of the conflicting entries is reported, events by their number
+        assert_eq!(
+            consolidate([holidays.clone(), events.clone()].concat()),
+            Err(Error::DuplicateDate {
+                file: "holidays".to_string(),
+                line_nr: 1,
+            })
+        );
+        assert_eq!(
+            consolidate([events, holidays].concat()),
+            Err(Error::DuplicateDate {
+                file: "calendar.ics".to_string(),
+                line_nr: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_ics_errors() {
+        assert!("Urlaub -> X".parse::<IcsRule>().is_err());
+        assert!("Urlaub U".parse::<IcsRule>().is_err());
+        let calendar = "BEGIN:VCALENDAR\r
+BEGIN:VEVENT\r
+DTSTART;VALUE=DATE:20180512\r
+DTEND;VALUE=DATE:20180510\r
+SUMMARY:Urlaub\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+        let result = parse_ics(
+            &mut std::io::BufReader::new(calendar.as_bytes()),
+            "tst_file",
+            &rules(),
+            &berlin(),
+        );
+        assert_eq!(
+            result,
+            Err(Error::ParseCalendar {
+                file: "tst_file".to_string(),
+                message: "Event 1 has an invalid DTEND".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_ics_duration() {
+        let date = |day| Date::from_ymd_opt(2018, 5, day).unwrap();
+        let vacation = DayType::Vacation {
+            description: "Urlaub".to_string(),
+        };
+        let entries = parse_events(
+            "BEGIN:VEVENT\r
+DTSTART;VALUE=DATE:20180507\r
+DURATION:P3D\r
+SUMMARY:Urlaub\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20180514T200000\r
+DURATION:PT5H\r
+SUMMARY:Urlaub\r
+END:VEVENT\r
+",
+        )
+        .unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                DayTypeEntry::new(date(7), vacation.clone(), true, "tst_file", 1),
+                DayTypeEntry::new(date(8), vacation.clone(), true, "tst_file", 1),
+                DayTypeEntry::new(date(9), vacation.clone(), true, "tst_file", 1),
+                DayTypeEntry::new(date(14), vacation.clone(), true, "tst_file", 2),
+                DayTypeEntry::new(date(15), vacation, true, "tst_file", 2),
+            ]
+        );
+        assert_eq!(
+            parse_events(
+                "BEGIN:VEVENT\r
+DTSTART;VALUE=DATE:20180507\r
+DURATION:-P1D\r
+SUMMARY:Urlaub\r
+END:VEVENT\r
+"
+            ),
+            Err(Error::ParseCalendar {
+                file: "tst_file".to_string(),
+                message: "Event 1 has an invalid DURATION".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_ics_utc() {
+        // 23:30 UTC is already the next day in Berlin, floating times stay as they are
+        let entries = parse_events(
+            "BEGIN:VEVENT\r
+DTSTART:20180514T233000Z\r
+DTEND:20180515T003000Z\r
+SUMMARY:Urlaub\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20180516T233000\r
+SUMMARY:Urlaub\r
+END:VEVENT\r
+",
+        )
+        .unwrap();
+        let vacation = DayType::Vacation {
+            description: "Urlaub".to_string(),
+        };
+        assert_eq!(
+            entries,
+            vec![
+                DayTypeEntry::new(
+                    Date::from_ymd_opt(2018, 5, 15).unwrap(),
+                    vacation.clone(),
+                    false,
+                    "tst_file",
+                    1
+                ),
+                DayTypeEntry::new(
+                    Date::from_ymd_opt(2018, 5, 16).unwrap(),
+                    vacation,
+                    false,
+                    "tst_file",
+                    2
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_ics_recurring_event() {
+        assert_eq!(
+            parse_events(
+                "BEGIN:VEVENT\r
+DTSTART;VALUE=DATE:20180507\r
+RRULE:FREQ=WEEKLY;COUNT=3\r
+SUMMARY:Urlaub\r
+END:VEVENT\r
+"
+            ),
+            Err(Error::ParseCalendar {
+                file: "tst_file".to_string(),
+                message: "Event 1 is recurring, which is not supported".to_string(),
+            })
+        );
+        // recurring events without a matching rule are ignored
+        assert_eq!(
+            parse_events(
+                "BEGIN:VEVENT\r
+DTSTART:20180507T090000\r
+RRULE:FREQ=WEEKLY\r
+SUMMARY:Team Meeting\r
+END:VEVENT\r
+"
+            ),
+            Ok(Vec::new())
+        );
+    }
+
+    fn export(entries: &[DayTypeEntry], privacy: Privacy) -> String {
+        let mut out = Vec::new();
+        write_ics(
+            &mut out,
+            entries,
+            &IcsExportConfig {
+                privacy,
+                summary_prefix: "Alice: ".to_string(),
+            },
+            &Date::from_ymd_opt(2018, 4, 1)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write_ics() {
+        let entries = required_time::parse_required_time(
+            &mut std::io::BufReader::new(
+                r"2018-05-07--2018-05-11 -- U Ostsee, Rügen
+2018-05-14 -- K Grippe"
+                    .as_bytes(),
+            ),
+            "tst_file",
+        )
+        .unwrap();
+        let ics = export(&entries, Privacy::Full);
+        let expected = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//belgoking//log_work//EN\r
+BEGIN:VEVENT\r
+UID:20180507-1@log_work\r
+DTSTAMP:20180401T120000Z\r
+DTSTART;VALUE=DATE:20180507\r
+DTEND;VALUE=DATE:20180512\r
+SUMMARY:Alice: Vacation: Ostsee\\,\r
+CATEGORIES:Vacation\r
+TRANSP:OPAQUE\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:20180514-2@log_work\r
+DTSTAMP:20180401T120000Z\r
+DTSTART;VALUE=DATE:20180514\r
+DTEND;VALUE=DATE:20180515\r
+SUMMARY:Alice: Sick: Grippe\r
+CATEGORIES:Sick\r
+TRANSP:OPAQUE\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+        assert_eq!(ics, expected);
+
+        let ics = export(&entries, Privacy::Busy);
+        assert!(ics.contains("SUMMARY:Alice: Absent\r\n"));
+        assert!(!ics.contains("Grippe"));
+        assert!(!ics.contains("CATEGORIES"));
+        assert!(export(&entries, Privacy::Type).contains("SUMMARY:Alice: Sick\r\n"));
+
+        // the exported events can be imported again
+        let rules: Vec<IcsRule> = ["Vacation -> U", "Sick -> K"]
+            .iter()
+            .map(|rule| rule.parse().unwrap())
+            .collect();
+        let imported = parse_ics(
+            &mut std::io::BufReader::new(export(&entries, Privacy::Type).as_bytes()),
+            "tst_file",
+            &rules,
+            &berlin(),
+        )
+        .unwrap();
+        let dates: Vec<_> = imported.iter().map(|entry| entry.date()).collect();
+        let expected_dates: Vec<_> = entries.iter().map(|entry| entry.date()).collect();
+        assert_eq!(dates, expected_dates);
+    }
+
+    #[test]
+    fn test_write_line_folds_long_lines() {
+        let mut out = Vec::new();
+        write_line(&mut out, &format!("SUMMARY:{}", "ä".repeat(40))).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert_eq!(lines[1].chars().next(), Some(' '));
+    }
+}
@@ -8,9 +8,27 @@ pub enum Error {
     HttpErrorStatusCode(reqwest::StatusCode),
     Conversion(core::num::TryFromIntError),
     Canceled,
+    /// The number of worklogs which could not be created, updated or deleted
+    Transmission(u32),
     Misc(String),
 }
 
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::JsonParsing(ref err) => write!(f, "JsonParsingError: {}", err),
+            Error::Network(ref err) => write!(f, "NetworkError: {}", err),
+            Error::HttpErrorStatusCode(ref status) => write!(f, "HttpErrorStatusCode: {}", status),
+            Error::Conversion(ref err) => write!(f, "ConversionError: {}", err),
+            Error::Canceled => write!(f, "Canceled"),
+            Error::Transmission(count) => {
+                write!(f, "TransmissionError: {} worklog(s) failed", count)
+            }
+            Error::Misc(ref s) => write!(f, "MiscError: {}", s),
+        }
+    }
+}
+
 impl From<serde_json::error::Error> for Error {
     fn from(err: serde_json::error::Error) -> Error {
         Error::JsonParsing(err)
@@ -63,24 +81,109 @@ impl TimeZone {
                 .fixed_offset(),
         }
     }
+
+    /// Converts a date and time given in UTC to the date and time in this timezone
+    pub fn utc_to_local(&self, utc_date_time: &chrono::NaiveDateTime) -> chrono::NaiveDateTime {
+        match self {
+            TimeZone::Local(tz) => {
+                chrono::TimeZone::from_utc_datetime(tz, utc_date_time).naive_local()
+            }
+            TimeZone::Tz(tz) => {
+                chrono::TimeZone::from_utc_datetime(tz, utc_date_time).naive_local()
+            }
+        }
+    }
+}
+
+/// How to answer the question whether already logged worklogs may be updated or deleted
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ConfirmationPolicy {
+    /// Ask on stdin
+    #[default]
+    Ask,
+    /// Update and delete without asking
+    Yes,
+    /// Leave the already logged worklogs untouched and only create the missing ones
+    NoReplace,
+}
+
+/// Controls how the worklogs are synchronized
+#[derive(Clone, Debug, Default)]
+pub struct SyncOptions {
+    /// Only perform the read-only requests and print the planned changes
+    pub dry_run: bool,
+    pub confirmation_policy: ConfirmationPolicy,
+}
+
+/// The way requests to the JIRA server are authenticated
+pub enum Authentication {
+    Basic {
+        username: String,
+        password: String,
+    },
+    /// A personal access token
+    Bearer {
+        token: String,
+    },
+}
+
+/// The kind of JIRA instance. Both differ in the REST API version, the way users are identified
+/// and the format of worklog comments.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum JiraFlavor {
+    /// JIRA Server or Data Center using the REST API v2
+    #[default]
+    Server,
+    /// JIRA Cloud using the REST API v3. Authenticate with the email address as username and an
+    /// API token as password.
+    Cloud,
+}
+
+impl std::str::FromStr for JiraFlavor {
+    type Err = super::Error;
+
+    fn from_str(s: &str) -> std::result::Result<JiraFlavor, super::Error> {
+        match s {
+            "server" => Ok(JiraFlavor::Server),
+            "cloud" => Ok(JiraFlavor::Cloud),
+            _ => Err(super::Error::CommandLine(format!(
+                "Unknown JIRA flavor '{}', expected one of 'server' or 'cloud'",
+                s
+            ))),
+        }
+    }
 }
 
 pub struct JiraConfig {
     pub base_url: String,
-    pub basic_auth_credentials: Option<(String, String)>,
+    pub authentication: Authentication,
     pub username: String,
     pub timezone: TimeZone,
+    pub flavor: JiraFlavor,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Eq, PartialEq, PartialOrd, Ord, Debug)]
-struct SessionHolder {
-    session: SessionDetails,
+impl JiraConfig {
+    fn api_path(&self) -> &'static str {
+        match self.flavor {
+            JiraFlavor::Server => "/rest/api/2",
+            JiraFlavor::Cloud => "/rest/api/3",
+        }
+    }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Eq, PartialEq, PartialOrd, Ord, Debug)]
-struct SessionDetails {
-    name: String, // Name of the cookie
-    value: String,
+/// How the worklogs of the current user are recognized
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum JiraUser {
+    /// JIRA Server identifies users by their name
+    Name(String),
+    /// JIRA Cloud identifies users by their account ID
+    AccountId(String),
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Eq, PartialEq, Debug)]
+struct Myself {
+    #[serde(rename = "accountId")]
+    account_id: String,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Eq, PartialEq, PartialOrd, Ord, Debug)]
@@ -98,8 +201,9 @@ struct ResponseWithWorklogs {
     worklogs: Vec<StoredWorklogEntry>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Eq, PartialEq, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Eq, PartialEq, Debug)]
 struct StoredWorklogEntry {
+    #[serde(default, deserialize_with = "deserialize_comment")]
     comment: String,
     started: DateTime,
     id: String,
@@ -110,12 +214,74 @@ struct StoredWorklogEntry {
     author: WorklogAuthor,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Eq, PartialEq, PartialOrd, Ord, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Eq, PartialEq, PartialOrd, Ord, Debug)]
 struct WorklogAuthor {
-    name: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default, rename = "accountId")]
+    account_id: Option<String>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Eq, PartialEq, Debug)]
+impl WorklogAuthor {
+    fn is(&self, user: &JiraUser) -> bool {
+        match user {
+            JiraUser::Name(name) => self.name.as_ref() == Some(name),
+            JiraUser::AccountId(account_id) => self.account_id.as_ref() == Some(account_id),
+        }
+    }
+}
+
+/// Reads a comment that is either a plain string (REST API v2) or a document in the Atlassian
+/// Document Format (REST API v3). Of the latter only the text is kept.
+fn deserialize_comment<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    fn collect_text(value: &serde_json::Value, text: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                if let Some(serde_json::Value::String(s)) = map.get("text") {
+                    text.push(s.clone());
+                }
+                if let Some(content) = map.get("content") {
+                    collect_text(content, text);
+                }
+            }
+            serde_json::Value::Array(values) => {
+                for value in values {
+                    collect_text(value, text);
+                }
+            }
+            _ => (),
+        }
+    }
+    let value = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+    match value {
+        serde_json::Value::String(s) => Ok(s),
+        serde_json::Value::Null => Ok(String::new()),
+        value => {
+            let mut text = Vec::new();
+            collect_text(&value, &mut text);
+            Ok(text.concat())
+        }
+    }
+}
+
+/// Wraps a plain text into a document in the Atlassian Document Format
+fn to_document(text: &str) -> serde_json::Value {
+    let content = if text.is_empty() {
+        serde_json::json!([])
+    } else {
+        serde_json::json!([{"type": "text", "text": text}])
+    };
+    serde_json::json!({
+        "type": "doc",
+        "version": 1,
+        "content": [{"type": "paragraph", "content": content}]
+    })
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Eq, PartialEq, Debug)]
 struct NewWorklogEntry {
     comment: String,
     #[serde(with = "my_date_format")]
@@ -124,6 +290,17 @@ struct NewWorklogEntry {
     time_spent_seconds: u64,
 }
 
+impl NewWorklogEntry {
+    /// The body of the request creating or updating the worklog
+    fn to_request_body(&self, flavor: JiraFlavor) -> Result<serde_json::Value> {
+        let mut body = serde_json::to_value(self)?;
+        if flavor == JiraFlavor::Cloud {
+            body["comment"] = to_document(&self.comment);
+        }
+        Ok(body)
+    }
+}
+
 mod my_date_format {
     use super::*;
     use serde::Deserialize as _;
@@ -148,13 +325,15 @@ mod my_date_format {
     }
 }
 
-fn opt_add_basic_auth(
+fn opt_add_auth(
     request_builder: reqwest::RequestBuilder,
-    basic_auth_credentials: &Option<(String, String)>,
+    authentication: &Authentication,
 ) -> reqwest::RequestBuilder {
-    match basic_auth_credentials {
-        Some((username, password)) => request_builder.basic_auth(username, Some(password)),
-        None => request_builder,
+    match authentication {
+        Authentication::Basic { username, password } => {
+            request_builder.basic_auth(username, Some(password))
+        }
+        Authentication::Bearer { token } => request_builder.bearer_auth(token),
     }
 }
 
@@ -163,9 +342,9 @@ async fn retrieve_json<T: for<'de> serde::Deserialize<'de>>(
     client: &reqwest::Client,
     jira_config: &JiraConfig,
 ) -> Result<T> {
-    let response = opt_add_basic_auth(
-        client.get(&format!("{}{}", jira_config.base_url, query_path)),
-        &jira_config.basic_auth_credentials,
+    let response = opt_add_auth(
+        client.get(format!("{}{}", jira_config.base_url, query_path)),
+        &jira_config.authentication,
     )
     .send()
     .await?;
@@ -182,14 +361,69 @@ async fn post_worklog(
     jira_config: &JiraConfig,
 ) -> Result<()> {
     println!("POSTING ISSUE {} ({:?})", issue_name, new_worklog);
-    let response = opt_add_basic_auth(
-        client.post(&format!(
-            "{}/rest/api/2/issue/{}/worklog",
-            jira_config.base_url, issue_name
+    let response = opt_add_auth(
+        client.post(format!(
+            "{}{}/issue/{}/worklog",
+            jira_config.base_url,
+            jira_config.api_path(),
+            issue_name
+        )),
+        &jira_config.authentication,
+    )
+    .json(&new_worklog.to_request_body(jira_config.flavor)?)
+    .send()
+    .await?;
+    if !response.status().is_success() {
+        return Err(Error::HttpErrorStatusCode(response.status()));
+    }
+    Ok(())
+}
+
+async fn put_worklog(
+    worklog: &StoredWorklogEntry,
+    new_worklog: &NewWorklogEntry,
+    client: &reqwest::Client,
+    jira_config: &JiraConfig,
+) -> Result<()> {
+    println!(
+        "UPDATING ISSUE {} WORKLOG {} ({:?})",
+        worklog.issue_id, worklog.id, new_worklog
+    );
+    let response = opt_add_auth(
+        client.put(format!(
+            "{}{}/issue/{}/worklog/{}",
+            jira_config.base_url,
+            jira_config.api_path(),
+            worklog.issue_id,
+            worklog.id
+        )),
+        &jira_config.authentication,
+    )
+    .json(&new_worklog.to_request_body(jira_config.flavor)?)
+    .send()
+    .await?;
+    if !response.status().is_success() {
+        return Err(Error::HttpErrorStatusCode(response.status()));
+    }
+    Ok(())
+}
+
+async fn delete_worklog(
+    worklog: &StoredWorklogEntry,
+    client: &reqwest::Client,
+    jira_config: &JiraConfig,
+) -> Result<()> {
+    println!("DELETING ISSUE {} WORKLOG {}", worklog.issue_id, worklog.id);
+    let response = opt_add_auth(
+        client.delete(format!(
+            "{}{}/issue/{}/worklog/{}",
+            jira_config.base_url,
+            jira_config.api_path(),
+            worklog.issue_id,
+            worklog.id
         )),
-        &jira_config.basic_auth_credentials,
+        &jira_config.authentication,
     )
-    .json(new_worklog)
     .send()
     .await?;
     if !response.status().is_success() {
@@ -204,10 +438,16 @@ async fn retrieve_issues_with_worklogs(
     jira_config: &JiraConfig,
 ) -> Result<Vec<String>> {
     // TODO: add request filter such that not all fields of the Tickets are retrieved
-    let uri = format!(
-        "/rest/api/2/search?jql=worklogAuthor%3DcurrentUser()+AND+worklogDate%3D{}",
-        day.format("%Y-%m-%d")
-    );
+    let uri = match jira_config.flavor {
+        JiraFlavor::Server => format!(
+            "/rest/api/2/search?jql=worklogAuthor%3DcurrentUser()+AND+worklogDate%3D{}",
+            day.format("%Y-%m-%d")
+        ),
+        JiraFlavor::Cloud => format!(
+            "/rest/api/3/search/jql?jql=worklogAuthor%3DcurrentUser()+AND+worklogDate%3D{}&fields=key",
+            day.format("%Y-%m-%d")
+        ),
+    };
     let issues = retrieve_json::<ResponseWithIssues>(&uri, client, jira_config)
         .await?
         .issues
@@ -222,7 +462,7 @@ async fn is_jira_issue(
     client: &reqwest::Client,
     jira_config: &JiraConfig,
 ) -> Result<bool> {
-    let uri = format!("/rest/api/2/issue/{}?fields=id", issue);
+    let uri = format!("{}/issue/{}?fields=id", jira_config.api_path(), issue);
     if let Err(e) = retrieve_json::<Issue>(&uri, client, jira_config).await {
         match e {
             Error::HttpErrorStatusCode(reqwest::StatusCode::NOT_FOUND) => return Ok(false),
@@ -240,10 +480,247 @@ fn has_jira_key_structure(candidate: &str) -> bool {
     RE.is_match(candidate)
 }
 
+/// A worklog that should exist in Jira according to the local work files
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct LocalWorklogEntry {
+    issue: String,
+    worklog: NewWorklogEntry,
+}
+
+/// The reason why an entry of a work file is not logged to Jira
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum SkipReason {
+    NotAJiraKey,
+    UnknownIssue,
+    ZeroDuration,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            SkipReason::NotAJiraKey => write!(f, "not a Jira key"),
+            SkipReason::UnknownIssue => write!(f, "unknown issue"),
+            SkipReason::ZeroDuration => write!(f, "zero duration"),
+        }
+    }
+}
+
+/// Tells why the entry is not logged to Jira, if so. The confirmed issues are the ones known to
+/// exist in Jira.
+fn skip_reason(
+    entry: &work_day::Entry,
+    confirmed_issues: &std::collections::BTreeSet<&String>,
+) -> Option<SkipReason> {
+    if !has_jira_key_structure(&entry.key) {
+        Some(SkipReason::NotAJiraKey)
+    } else if !confirmed_issues.contains(&entry.key) {
+        Some(SkipReason::UnknownIssue)
+    } else if entry.duration.is_zero() {
+        Some(SkipReason::ZeroDuration)
+    } else {
+        None
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct SkippedEntry {
+    date: super::Date,
+    entry: work_day::Entry,
+    reason: SkipReason,
+}
+
+/// The changes that are necessary to bring the worklogs in Jira in line with the local ones
+#[derive(Debug, Default, Eq, PartialEq)]
+struct SyncPlan {
+    create: Vec<LocalWorklogEntry>,
+    update: Vec<(StoredWorklogEntry, LocalWorklogEntry)>,
+    delete: Vec<StoredWorklogEntry>,
+    unchanged: Vec<StoredWorklogEntry>,
+}
+
+impl SyncPlan {
+    fn modifies_stored_worklogs(&self) -> bool {
+        !self.update.is_empty() || !self.delete.is_empty()
+    }
+
+    /// Keeps the worklogs that would be updated or deleted. The local worklogs overlapping a kept
+    /// worklog of the same issue are not created (they would log the time twice) but returned.
+    fn keep_stored_worklogs(&mut self) -> Vec<LocalWorklogEntry> {
+        self.unchanged.append(&mut self.delete);
+        self.unchanged
+            .extend(self.update.drain(..).map(|(worklog, _)| worklog));
+        let overlaps = |stored: &StoredWorklogEntry, local: &LocalWorklogEntry| {
+            let end = |started: &DateTime, seconds: u64| {
+                *started + chrono::Duration::seconds(i64::try_from(seconds).unwrap_or(i64::MAX))
+            };
+            stored.issue_id == local.issue
+                && stored.started < end(&local.worklog.started, local.worklog.time_spent_seconds)
+                && local.worklog.started < end(&stored.started, stored.time_spent_seconds)
+        };
+        let (overlapping, create) = self
+            .create
+            .drain(..)
+            .partition(|local| self.unchanged.iter().any(|stored| overlaps(stored, local)));
+        self.create = create;
+        overlapping
+    }
+}
+
+/// Applies the confirmation policy to a plan which modifies stored worklogs. `ask` is only called
+/// for ConfirmationPolicy::Ask and tells whether the user agreed. Returns the local worklogs which
+/// are not created because they overlap a kept worklog.
+fn apply_confirmation_policy(
+    plan: &mut SyncPlan,
+    policy: ConfirmationPolicy,
+    ask: &mut dyn FnMut() -> Result<bool>,
+) -> Result<Vec<LocalWorklogEntry>> {
+    match policy {
+        ConfirmationPolicy::Ask => {
+            println!("Do you want to update and delete them? (yN)");
+            if !ask()? {
+                println!("Aborting!");
+                return Err(Error::Canceled);
+            }
+            Ok(Vec::new())
+        }
+        ConfirmationPolicy::Yes => {
+            println!("Updating and deleting them without confirmation");
+            Ok(Vec::new())
+        }
+        ConfirmationPolicy::NoReplace => {
+            println!("Keeping them as they are");
+            Ok(plan.keep_stored_worklogs())
+        }
+    }
+}
+
+/// Matches the stored worklogs with the local ones. Worklogs with the same issue, start and
+/// duration are kept (or only get their comment updated), worklogs with the same issue and start
+/// get their duration updated. All remaining stored worklogs are deleted and the remaining local
+/// worklogs are created.
+fn compute_sync_plan(
+    mut stored: Vec<StoredWorklogEntry>,
+    mut local: Vec<LocalWorklogEntry>,
+) -> SyncPlan {
+    let mut plan = SyncPlan::default();
+    let matches_exactly = |stored: &StoredWorklogEntry, local: &LocalWorklogEntry| {
+        stored.issue_id == local.issue
+            && stored.started == local.worklog.started
+            && stored.time_spent_seconds == local.worklog.time_spent_seconds
+    };
+    let matches_start = |stored: &StoredWorklogEntry, local: &LocalWorklogEntry| {
+        stored.issue_id == local.issue && stored.started == local.worklog.started
+    };
+
+    let mut remaining_stored = Vec::new();
+    for stored_entry in stored.drain(..) {
+        match local
+            .iter()
+            .position(|local_entry| matches_exactly(&stored_entry, local_entry))
+        {
+            Some(pos) => {
+                let local_entry = local.remove(pos);
+                if stored_entry.comment == local_entry.worklog.comment {
+                    plan.unchanged.push(stored_entry);
+                } else {
+                    plan.update.push((stored_entry, local_entry));
+                }
+            }
+            None => remaining_stored.push(stored_entry),
+        }
+    }
+    for stored_entry in remaining_stored {
+        match local
+            .iter()
+            .position(|local_entry| matches_start(&stored_entry, local_entry))
+        {
+            Some(pos) => {
+                let local_entry = local.remove(pos);
+                plan.update.push((stored_entry, local_entry));
+            }
+            None => plan.delete.push(stored_entry),
+        }
+    }
+    plan.create = local;
+    plan
+}
+
+/// Prints all planned changes as a table
+fn print_sync_plan(plan: &SyncPlan, skipped: &[SkippedEntry]) {
+    fn print_row(action: &str, issue: &str, started: &str, seconds: u64, details: &str) {
+        println!(
+            "{:8} {:12} {:25} {:>6}m {}",
+            action,
+            issue,
+            started,
+            seconds / 60,
+            details
+        );
+    }
+    println!(
+        "{:8} {:12} {:25} {:>7} COMMENT/REASON",
+        "ACTION", "ISSUE", "START", "TIME"
+    );
+    for worklog in &plan.delete {
+        print_row(
+            "DELETE",
+            &worklog.issue_id,
+            &worklog.started.to_string(),
+            worklog.time_spent_seconds,
+            &worklog.comment,
+        );
+    }
+    for (worklog, new_worklog) in &plan.update {
+        print_row(
+            "UPDATE",
+            &worklog.issue_id,
+            &worklog.started.to_string(),
+            new_worklog.worklog.time_spent_seconds,
+            &format!(
+                "{} (was {}m '{}')",
+                new_worklog.worklog.comment,
+                worklog.time_spent_seconds / 60,
+                worklog.comment
+            ),
+        );
+    }
+    for new_worklog in &plan.create {
+        print_row(
+            "CREATE",
+            &new_worklog.issue,
+            &new_worklog.worklog.started.to_string(),
+            new_worklog.worklog.time_spent_seconds,
+            &new_worklog.worklog.comment,
+        );
+    }
+    for worklog in &plan.unchanged {
+        print_row(
+            "KEEP",
+            &worklog.issue_id,
+            &worklog.started.to_string(),
+            worklog.time_spent_seconds,
+            &worklog.comment,
+        );
+    }
+    for skipped_entry in skipped {
+        print_row(
+            "SKIP",
+            &skipped_entry.entry.key,
+            &skipped_entry
+                .date
+                .and_time(skipped_entry.entry.start_ts)
+                .to_string(),
+            u64::try_from(skipped_entry.entry.duration.num_seconds()).unwrap_or(0),
+            &skipped_entry.reason.to_string(),
+        );
+    }
+}
+
 async fn do_update_logging_for_days_with_session(
     days: &std::vec::Vec<&work_day::WorkDay>,
     client: &reqwest::Client,
     jira_config: &JiraConfig,
+    sync_options: &SyncOptions,
 ) -> Result<()> {
     let mut issues_with_old_logs = std::collections::BTreeSet::new();
     println!(
@@ -251,28 +728,40 @@ async fn do_update_logging_for_days_with_session(
         days.len()
     );
     for day in days {
-        let mut issues =
-            retrieve_issues_with_worklogs(&day.date, client, jira_config).await?;
+        let mut issues = retrieve_issues_with_worklogs(&day.date, client, jira_config).await?;
         issues_with_old_logs.extend(issues.drain(..));
     }
 
     let relevant_days: std::collections::HashSet<_> = days.iter().map(|day| day.date).collect();
 
+    let user = match jira_config.flavor {
+        JiraFlavor::Server => JiraUser::Name(jira_config.username.clone()),
+        JiraFlavor::Cloud => {
+            let uri = format!("{}/myself", jira_config.api_path());
+            JiraUser::AccountId(
+                retrieve_json::<Myself>(&uri, client, jira_config)
+                    .await?
+                    .account_id,
+            )
+        }
+    };
+
     let my_logs = {
         let mut my_logs = std::vec::Vec::new();
         for ref issue in &issues_with_old_logs {
-            let uri = format!("/rest/api/2/issue/{}/worklog", issue);
+            let uri = format!("{}/issue/{}/worklog", jira_config.api_path(), issue);
             let mut worklogs =
-                retrieve_json::<ResponseWithWorklogs>(&uri, client, jira_config)
-                    .await?;
+                retrieve_json::<ResponseWithWorklogs>(&uri, client, jira_config).await?;
             let mut worklogs: std::vec::Vec<_> = worklogs
                 .worklogs
                 .drain(..)
                 .filter(|entry| {
-                    entry.author.name == jira_config.username
-                        && relevant_days.contains(&entry.started.date_naive())
+                    entry.author.is(&user) && relevant_days.contains(&entry.started.date_naive())
+                })
+                .map(|entry| StoredWorklogEntry {
+                    issue_id: issue.to_string(),
+                    ..entry
                 })
-                .map(|entry| StoredWorklogEntry{ issue_id: issue.to_string(), ..entry})
                 .collect();
             my_logs.append(&mut worklogs);
         }
@@ -284,48 +773,20 @@ async fn do_update_logging_for_days_with_session(
         jira_config.username
     );
 
-    // println!("Would delete: {:?}", my_logs.iter().map(|ref log| format!("{}_{}", log.issue_id, log.id)).collect::<std::vec::Vec<_>>());
-    if !my_logs.is_empty() {
-        println!("The following entries have already been logged to the current day:");
-        for worklog in &my_logs {
-            println!(
-                "issue={} start_time='{}' duration={}(secs)?",
-                worklog.issue_id, worklog.started, worklog.time_spent_seconds
-            );
-        }
-        println!("Do you want to delete them and replace them with the current ones? (yN)");
-        // this blocks on purpose (see documentation of tokio::io::stdin())
-        let mut buf = String::new();
-        std::io::stdin().read_line(&mut buf)?;
-        if buf.as_str() != "y\n" {
-            println!("Aborting!");
-            return Err(Error::Canceled);
-        }
-        for worklog in &my_logs {
-            let uri = format!(
-                "{}/rest/api/2/issue/{}/worklog/{}",
-                jira_config.base_url, worklog.issue_id, worklog.id
-            );
-            let response = opt_add_basic_auth(
-                client.delete(uri.as_str()),
-                &jira_config.basic_auth_credentials,
-            )
-            .send()
-            .await
-            .map_err(|err| {
-                println!("ERR: {:?}", err);
-                err
-            })?;
-            if !response.status().is_success() {
-                return Err(Error::HttpErrorStatusCode(response.status()));
-            }
-        }
-    }
+    // entries naming several issues are logged separately to each of them
+    let entries: std::vec::Vec<_> = days
+        .iter()
+        .flat_map(|day| {
+            day.entries
+                .iter()
+                .flat_map(|entry| entry.split())
+                .map(move |entry| (day.date, entry))
+        })
+        .collect();
 
-    let possible_issue_names: std::collections::BTreeSet<_> = days
+    let possible_issue_names: std::collections::BTreeSet<_> = entries
         .iter()
-        .flat_map(|day| day.entries.iter())
-        .map(|entry| &entry.key)
+        .map(|(_, entry)| &entry.key)
         .filter(|issue_name| has_jira_key_structure(issue_name.as_str()))
         .collect();
 
@@ -352,62 +813,380 @@ async fn do_update_logging_for_days_with_session(
         }
     }
 
-    // perform the worklogs
-    let mut transmitted = std::vec::Vec::new();
-    let mut without_issue = std::vec::Vec::new();
-    let mut with_transmission_error = std::vec::Vec::new();
-    for day in days {
-        for entry in &day.entries {
-            if confirmed_issues.contains(&entry.key) && !entry.duration.is_zero() {
-                let new_worklog = NewWorklogEntry {
+    // collect the worklogs that should exist
+    let mut local_logs = std::vec::Vec::new();
+    let mut skipped = std::vec::Vec::new();
+    for (date, entry) in &entries {
+        match skip_reason(entry, &confirmed_issues) {
+            Some(reason) => skipped.push(SkippedEntry {
+                date: *date,
+                entry: entry.clone(),
+                reason,
+            }),
+            None => local_logs.push(LocalWorklogEntry {
+                issue: entry.key.clone(),
+                worklog: NewWorklogEntry {
                     comment: itertools::join(&entry.sub_keys, " "),
                     started: jira_config
                         .timezone
-                        .to_local_date_time(&day.date.and_time(entry.start_ts)),
+                        .to_local_date_time(&date.and_time(entry.start_ts)),
                     time_spent_seconds: u64::try_from(entry.duration.num_seconds())?,
-                };
-                match post_worklog(
-                    entry.key.as_str(),
-                    &new_worklog,
-                    client,
-                    jira_config,
-                )
-                .await
-                {
-                    Ok(()) => transmitted.push(entry.clone()),
-                    Err(e) => {
-                        println!("Error transmitting {:?}: {:?}", entry, e);
-                        with_transmission_error.push(entry.clone());
-                    }
-                }
-            } else {
-                without_issue.push(entry.clone());
+                },
+            }),
+        }
+    }
+
+    let plan = compute_sync_plan(my_logs, local_logs);
+    if sync_options.dry_run {
+        print_sync_plan(&plan, &skipped);
+        println!("Dry run, nothing has been sent to JIRA");
+        return Ok(());
+    }
+    let mut plan = plan;
+    let mut overlapping = Vec::new();
+    if plan.modifies_stored_worklogs() {
+        println!("The following entries have already been logged to the given days:");
+        for (worklog, new_worklog) in &plan.update {
+            println!(
+                "UPDATE issue={} start_time='{}' duration={}(secs) -> duration={}(secs) comment='{}'",
+                worklog.issue_id,
+                worklog.started,
+                worklog.time_spent_seconds,
+                new_worklog.worklog.time_spent_seconds,
+                new_worklog.worklog.comment
+            );
+        }
+        for worklog in &plan.delete {
+            println!(
+                "DELETE issue={} start_time='{}' duration={}(secs)",
+                worklog.issue_id, worklog.started, worklog.time_spent_seconds
+            );
+        }
+        overlapping =
+            apply_confirmation_policy(&mut plan, sync_options.confirmation_policy, &mut || {
+                // this blocks on purpose (see documentation of tokio::io::stdin())
+                let mut buf = String::new();
+                std::io::stdin().read_line(&mut buf)?;
+                Ok(buf.as_str() == "y\n")
+            })?;
+        for new_worklog in &overlapping {
+            println!(
+                "SKIP issue={} start_time='{}' duration={}(secs) overlaps a kept worklog",
+                new_worklog.issue,
+                new_worklog.worklog.started,
+                new_worklog.worklog.time_spent_seconds
+            );
+        }
+    }
+
+    // perform the changes
+    let mut with_transmission_error = 0;
+    let mut deleted = 0;
+    for worklog in &plan.delete {
+        match delete_worklog(worklog, client, jira_config).await {
+            Ok(()) => deleted += 1,
+            Err(e) => {
+                println!("Error deleting {:?}: {:?}", worklog, e);
+                with_transmission_error += 1;
+            }
+        }
+    }
+    let mut updated = 0;
+    for (worklog, new_worklog) in &plan.update {
+        match put_worklog(worklog, &new_worklog.worklog, client, jira_config).await {
+            Ok(()) => updated += 1,
+            Err(e) => {
+                println!("Error updating {:?}: {:?}", worklog, e);
+                with_transmission_error += 1;
+            }
+        }
+    }
+    let mut created = 0;
+    for new_worklog in &plan.create {
+        match post_worklog(
+            &new_worklog.issue,
+            &new_worklog.worklog,
+            client,
+            jira_config,
+        )
+        .await
+        {
+            Ok(()) => created += 1,
+            Err(e) => {
+                println!("Error transmitting {:?}: {:?}", new_worklog, e);
+                with_transmission_error += 1;
             }
         }
     }
-    println!("Added {} worklog entries, ignored {} because of they were not correct, and {} transmission errors",
-             transmitted.len(), without_issue.len(), with_transmission_error.len());
+    println!("Added {} worklog entries, updated {}, deleted {}, kept {} unchanged, ignored {} because of they were not correct, {} because they overlap a kept one, and {} transmission errors",
+             created, updated, deleted, plan.unchanged.len(), skipped.len(), overlapping.len(), with_transmission_error);
 
+    if with_transmission_error > 0 {
+        return Err(Error::Transmission(with_transmission_error));
+    }
     Ok(())
 }
 
 async fn do_update_logging_for_days(
     days: &std::vec::Vec<&work_day::WorkDay>,
     jira_config: &JiraConfig,
+    sync_options: &SyncOptions,
 ) -> Result<()> {
     let client = reqwest::Client::new();
-    do_update_logging_for_days_with_session(
-        days,
-        &client,
-        jira_config,
-    )
-    .await
+    do_update_logging_for_days_with_session(days, &client, jira_config, sync_options).await
 }
 
 pub fn update_logging_for_days(
     days: &std::vec::Vec<&work_day::WorkDay>,
     jira_config: &JiraConfig,
+    sync_options: &SyncOptions,
 ) -> Result<()> {
     let runtime = tokio::runtime::Runtime::new().expect("Failed to instantiate tokio runtime");
-    runtime.block_on(do_update_logging_for_days(days, jira_config))
+    runtime.block_on(do_update_logging_for_days(days, jira_config, sync_options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn started(hour: u32, minute: u32) -> DateTime {
+        chrono::DateTime::parse_from_rfc3339(&format!(
+            "2018-05-09T{:02}:{:02}:00+02:00",
+            hour, minute
+        ))
+        .unwrap()
+    }
+
+    fn stored(
+        id: &str,
+        issue: &str,
+        start: (u32, u32),
+        secs: u64,
+        comment: &str,
+    ) -> StoredWorklogEntry {
+        StoredWorklogEntry {
+            comment: comment.to_string(),
+            started: started(start.0, start.1),
+            id: id.to_string(),
+            time_spent_seconds: secs,
+            issue_id: issue.to_string(),
+            author: WorklogAuthor {
+                name: Some("me".to_string()),
+                account_id: None,
+            },
+        }
+    }
+
+    fn local(issue: &str, start: (u32, u32), secs: u64, comment: &str) -> LocalWorklogEntry {
+        LocalWorklogEntry {
+            issue: issue.to_string(),
+            worklog: NewWorklogEntry {
+                comment: comment.to_string(),
+                started: started(start.0, start.1),
+                time_spent_seconds: secs,
+            },
+        }
+    }
+
+    #[test]
+    fn test_compute_sync_plan() {
+        let stored_entries = vec![
+            stored("1", "FOO-1", (8, 0), 3600, "a"),
+            stored("2", "FOO-1", (9, 0), 3600, "b"),
+            stored("3", "FOO-2", (10, 0), 3600, "c"),
+            stored("4", "FOO-3", (11, 0), 3600, "d"),
+        ];
+        let local_entries = vec![
+            local("FOO-1", (8, 0), 3600, "a"),
+            local("FOO-1", (9, 0), 3600, "changed"),
+            local("FOO-2", (10, 0), 1800, "c"),
+            local("FOO-4", (11, 0), 3600, "d"),
+        ];
+        let plan = compute_sync_plan(stored_entries, local_entries);
+        assert_eq!(
+            plan,
+            SyncPlan {
+                create: vec![local("FOO-4", (11, 0), 3600, "d")],
+                update: vec![
+                    (
+                        stored("2", "FOO-1", (9, 0), 3600, "b"),
+                        local("FOO-1", (9, 0), 3600, "changed")
+                    ),
+                    (
+                        stored("3", "FOO-2", (10, 0), 3600, "c"),
+                        local("FOO-2", (10, 0), 1800, "c")
+                    ),
+                ],
+                delete: vec![stored("4", "FOO-3", (11, 0), 3600, "d")],
+                unchanged: vec![stored("1", "FOO-1", (8, 0), 3600, "a")],
+            }
+        );
+        assert!(plan.modifies_stored_worklogs());
+    }
+
+    #[test]
+    fn test_compute_sync_plan_prefers_exact_matches() {
+        let stored_entries = vec![
+            stored("1", "FOO-1", (8, 0), 1800, "a"),
+            stored("2", "FOO-1", (8, 0), 3600, "a"),
+        ];
+        let local_entries = vec![local("FOO-1", (8, 0), 3600, "a")];
+        let plan = compute_sync_plan(stored_entries, local_entries);
+        assert_eq!(
+            plan,
+            SyncPlan {
+                create: Vec::new(),
+                update: Vec::new(),
+                delete: vec![stored("1", "FOO-1", (8, 0), 1800, "a")],
+                unchanged: vec![stored("2", "FOO-1", (8, 0), 3600, "a")],
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialize_document_comment() {
+        let txt = r#"{"comment":{"type":"doc","version":1,"content":[{"type":"paragraph",
+            "content":[{"type":"text","text":"Foo "},{"type":"text","text":"bar"}]}]},
+            "started":"2018-05-09T08:00:00.000+02:00","id":"1","timeSpentSeconds":60,
+            "issueId":"100","author":{"accountId":"abc"}}"#;
+        let worklog: StoredWorklogEntry = serde_json::from_str(txt).unwrap();
+        assert_eq!(worklog.comment, "Foo bar");
+        assert!(worklog.author.is(&JiraUser::AccountId("abc".to_string())));
+        assert!(!worklog.author.is(&JiraUser::Name("abc".to_string())));
+    }
+
+    #[test]
+    fn test_request_body_for_cloud() {
+        let worklog = local("FOO-1", (8, 0), 60, "Foo").worklog;
+        let body = worklog.to_request_body(JiraFlavor::Cloud).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "comment": {
+                    "type": "doc",
+                    "version": 1,
+                    "content": [{"type": "paragraph", "content": [{"type": "text", "text": "Foo"}]}]
+                },
+                "started": "2018-05-09T08:00:00.000+0200",
+                "timeSpentSeconds": 60
+            })
+        );
+        let body = worklog.to_request_body(JiraFlavor::Server).unwrap();
+        assert_eq!(body["comment"], serde_json::json!("Foo"));
+    }
+
+    #[test]
+    fn test_compute_sync_plan_only_new_entries() {
+        let plan = compute_sync_plan(Vec::new(), vec![local("FOO-1", (8, 0), 60, "")]);
+        assert!(!plan.modifies_stored_worklogs());
+        assert_eq!(plan.create, vec![local("FOO-1", (8, 0), 60, "")]);
+    }
+
+    fn entry(key: &str, minutes: i64) -> work_day::Entry {
+        work_day::Entry {
+            start_ts: chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            duration: chrono::Duration::minutes(minutes),
+            key: key.to_string(),
+            sub_keys: Vec::new(),
+            alias: None,
+            raw_data: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_skip_reason() {
+        let known = "FOO-1".to_string();
+        let confirmed_issues = [&known].into_iter().collect();
+        assert_eq!(skip_reason(&entry("FOO-1", 30), &confirmed_issues), None);
+        assert_eq!(
+            skip_reason(&entry("Pause", 30), &confirmed_issues),
+            Some(SkipReason::NotAJiraKey)
+        );
+        assert_eq!(
+            skip_reason(&entry("FOO-2", 30), &confirmed_issues),
+            Some(SkipReason::UnknownIssue)
+        );
+        assert_eq!(
+            skip_reason(&entry("FOO-1", 0), &confirmed_issues),
+            Some(SkipReason::ZeroDuration)
+        );
+        // the key is checked before the duration
+        assert_eq!(
+            skip_reason(&entry("FOO-2", 0), &confirmed_issues),
+            Some(SkipReason::UnknownIssue)
+        );
+    }
+
+    /// A plan updating FOO-1 (with a second local entry overlapping the stored one), deleting
+    /// FOO-3 and creating FOO-4
+    fn plan_modifying_stored_worklogs() -> SyncPlan {
+        compute_sync_plan(
+            vec![
+                stored("1", "FOO-1", (8, 0), 3600, "a"),
+                stored("2", "FOO-2", (10, 0), 3600, "b"),
+                stored("3", "FOO-3", (11, 0), 3600, "c"),
+            ],
+            vec![
+                local("FOO-1", (8, 0), 1800, "a"),
+                local("FOO-1", (8, 30), 1800, "a"),
+                local("FOO-2", (10, 0), 3600, "b"),
+                local("FOO-4", (11, 0), 3600, "d"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_confirmation_policy_ask() {
+        let mut plan = plan_modifying_stored_worklogs();
+        let overlapping =
+            apply_confirmation_policy(&mut plan, ConfirmationPolicy::Ask, &mut || Ok(true));
+        assert_eq!(overlapping.unwrap(), Vec::new());
+        assert_eq!(plan, plan_modifying_stored_worklogs());
+
+        let mut asked = false;
+        let result = apply_confirmation_policy(&mut plan, ConfirmationPolicy::Ask, &mut || {
+            asked = true;
+            Ok(false)
+        });
+        assert!(matches!(result, Err(Error::Canceled)));
+        assert!(asked);
+    }
+
+    #[test]
+    fn test_confirmation_policy_yes() {
+        let mut plan = plan_modifying_stored_worklogs();
+        let overlapping =
+            apply_confirmation_policy(&mut plan, ConfirmationPolicy::Yes, &mut || {
+                panic!("must not ask")
+            });
+        assert_eq!(overlapping.unwrap(), Vec::new());
+        assert_eq!(plan, plan_modifying_stored_worklogs());
+    }
+
+    #[test]
+    fn test_confirmation_policy_no_replace() {
+        let mut plan = plan_modifying_stored_worklogs();
+        let overlapping =
+            apply_confirmation_policy(&mut plan, ConfirmationPolicy::NoReplace, &mut || {
+                panic!("must not ask")
+            });
+        assert_eq!(
+            overlapping.unwrap(),
+            vec![local("FOO-1", (8, 30), 1800, "a")]
+        );
+        assert_eq!(
+            plan,
+            SyncPlan {
+                create: vec![local("FOO-4", (11, 0), 3600, "d")],
+                update: Vec::new(),
+                delete: Vec::new(),
+                unchanged: vec![
+                    stored("2", "FOO-2", (10, 0), 3600, "b"),
+                    stored("3", "FOO-3", (11, 0), 3600, "c"),
+                    stored("1", "FOO-1", (8, 0), 3600, "a"),
+                ],
+            }
+        );
+        assert!(!plan.modifies_stored_worklogs());
+    }
 }
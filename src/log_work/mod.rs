@@ -1,7 +1,21 @@
+pub mod absence;
+pub mod alias;
+pub mod balance;
+pub mod compliance;
+pub mod credentials;
+pub mod csv;
+pub mod ics;
 pub mod jira;
+pub mod projection;
+pub mod public_holidays;
+pub mod report;
 pub mod required_time;
+pub mod schedule;
+pub mod status;
 pub mod util;
+pub mod vacation;
 pub mod work_day;
+pub mod work_file;
 
 extern crate chrono;
 //use chrono::TimeZone;
@@ -13,15 +27,27 @@ type Time = chrono::NaiveTime;
 #[derive(Debug)]
 pub enum Error {
     CommandLine(String),
+    Credentials(String),
     IO(std::io::Error),
     ParseInt(std::num::ParseIntError),
     InvalidFileName {
         file: std::path::PathBuf,
     },
+    WorkFileExists {
+        file: std::path::PathBuf,
+    },
     ParseDayType {
         file: String,
         line_nr: u32,
     },
+    ParseAlias {
+        file: String,
+        line_nr: u32,
+    },
+    ParseCalendar {
+        file: String,
+        message: String,
+    },
     ParseDay,
     ParseTime,
     TimeNotMonotonic {
@@ -54,12 +80,17 @@ impl PartialEq for Error {
     fn eq(&self, other: &Error) -> bool {
         match (self, other) {
             (Error::CommandLine(ref s), Error::CommandLine(ref o)) => s == o,
+            (Error::Credentials(ref s), Error::Credentials(ref o)) => s == o,
             (Error::IO(_), Error::IO(_)) => true,
             (Error::ParseInt(_), Error::ParseInt(_)) => true,
             (
                 Error::InvalidFileName { file: ref s_file },
                 Error::InvalidFileName { file: ref o_file },
             ) => s_file == o_file,
+            (
+                Error::WorkFileExists { file: ref s_file },
+                Error::WorkFileExists { file: ref o_file },
+            ) => s_file == o_file,
             (
                 Error::ParseDayType {
                     file: ref s_file,
@@ -70,6 +101,26 @@ impl PartialEq for Error {
                     line_nr: o_line_nr,
                 },
             ) => s_file == o_file && s_line_nr == o_line_nr,
+            (
+                Error::ParseAlias {
+                    file: ref s_file,
+                    line_nr: s_line_nr,
+                },
+                Error::ParseAlias {
+                    file: ref o_file,
+                    line_nr: o_line_nr,
+                },
+            ) => s_file == o_file && s_line_nr == o_line_nr,
+            (
+                Error::ParseCalendar {
+                    file: ref s_file,
+                    message: ref s_message,
+                },
+                Error::ParseCalendar {
+                    file: ref o_file,
+                    message: ref o_message,
+                },
+            ) => s_file == o_file && s_message == o_message,
             (Error::ParseDay, &Error::ParseDay) => true,
             (Error::ParseTime, &Error::ParseTime) => true,
             (
@@ -137,15 +188,27 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match *self {
             Error::CommandLine(ref s) => write!(f, "CommandLineError: {}", s),
+            Error::Credentials(ref s) => write!(f, "CredentialsError: {}", s),
             Error::IO(ref err) => write!(f, "IOError: {}", err),
             Error::ParseInt(ref err) => write!(f, "ParseIntError: {}", err),
             Error::InvalidFileName { ref file } => {
                 write!(f, "InvalidFileNameError: {:?}", file)
             }
+            Error::WorkFileExists { ref file } => {
+                write!(f, "WorkFileExistsError: {:?}", file)
+            }
             Error::ParseDayType {
                 ref file,
                 ref line_nr,
             } => write!(f, "ParseDayTypeError: {}:{}", file, line_nr),
+            Error::ParseAlias {
+                ref file,
+                ref line_nr,
+            } => write!(f, "ParseAliasError: {}:{}", file, line_nr),
+            Error::ParseCalendar {
+                ref file,
+                ref message,
+            } => write!(f, "ParseCalendarError: {}: {}", file, message),
             Error::ParseDay => write!(f, "ParseDay"),
             Error::ParseTime => write!(f, "ParseHour"),
             Error::TimeNotMonotonic {
@@ -0,0 +1,311 @@
+use super::compliance;
+use super::work_day;
+use super::*;
+
+/// When the work of a day in progress may end
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LeaveProjection {
+    pub now: DateTime,
+    /// The work so far including the open entry
+    pub worked: chrono::Duration,
+    pub required: chrono::Duration,
+    pub duration_of_day: chrono::Duration,
+    /// The earliest time at which the required time is worked
+    pub leave_at: DateTime,
+    /// The break still required until `leave_at`
+    pub missing_break: chrono::Duration,
+    /// The balance of the flextime account before the day
+    pub balance_before: chrono::Duration,
+    /// The earliest time at which the balance of the flextime account is not negative
+    pub balanced_leave_at: DateTime,
+    /// The break still required until `balanced_leave_at`
+    pub balanced_missing_break: chrono::Duration,
+    /// Whether a balance of 0 requires more than the maximal work per day, in which case
+    /// `balanced_leave_at` is the time at which the maximal work is reached
+    pub balanced_exceeds_max_daily_work: bool,
+}
+
+impl LeaveProjection {
+    fn format_duration(&self, duration: chrono::Duration) -> util::WorkDuration {
+        util::WorkDuration {
+            duration,
+            duration_of_day: self.duration_of_day,
+        }
+    }
+
+    fn format_time(&self, ts: &DateTime) -> String {
+        if ts.date() == self.now.date() {
+            ts.format("%H:%M").to_string()
+        } else {
+            ts.format("%F %H:%M").to_string()
+        }
+    }
+}
+
+impl std::fmt::Display for LeaveProjection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "= Leave time at {}:", self.now.format("%F (%a) %H:%M"))?;
+        writeln!(
+            f,
+            "{:20}: {:>20}",
+            "Worked so far",
+            self.format_duration(self.worked)
+        )?;
+        writeln!(
+            f,
+            "{:20}: {:>20}",
+            "Required",
+            self.format_duration(self.required)
+        )?;
+        writeln!(
+            f,
+            "{:20}: {:>20}",
+            "Remaining",
+            self.format_duration(std::cmp::max(
+                self.required - self.worked,
+                chrono::Duration::zero()
+            ))
+        )?;
+        writeln!(
+            f,
+            "{:20}: {:>20}",
+            "Missing break",
+            self.format_duration(self.missing_break)
+        )?;
+        writeln!(
+            f,
+            "{:20}: {:>20}",
+            "Leave at",
+            self.format_time(&self.leave_at)
+        )?;
+        writeln!(
+            f,
+            "{:20}: {:>20}",
+            "Balance before",
+            self.format_duration(self.balance_before)
+        )?;
+        write!(
+            f,
+            "{:20}: {:>20}",
+            "Leave at balance 0",
+            self.format_time(&self.balanced_leave_at)
+        )?;
+        if self.balanced_exceeds_max_daily_work {
+            write!(f, " (maximal work per day reached)")?;
+        }
+        Ok(())
+    }
+}
+
+/// The time at which `target` (but at most the maximal work per day) is worked and the break still
+/// missing until then, if the work goes on (or is resumed) at `now`
+fn leave_time(
+    now: &DateTime,
+    worked: chrono::Duration,
+    breaks: chrono::Duration,
+    on_break: bool,
+    target: chrono::Duration,
+    config: &compliance::ComplianceConfig,
+) -> (DateTime, chrono::Duration) {
+    let target = std::cmp::min(target, config.max_daily_work);
+    let remaining = target - worked;
+    if remaining <= chrono::Duration::zero() {
+        return (*now, chrono::Duration::zero());
+    }
+    let mut missing_break = compliance::required_break(&config.break_rules, target) - breaks;
+    if missing_break <= chrono::Duration::zero() {
+        missing_break = chrono::Duration::zero();
+    } else if !on_break {
+        // a new break only counts if it is long enough, a running one is just extended
+        missing_break = std::cmp::max(missing_break, config.min_break);
+    }
+    (*now + remaining + missing_break, missing_break)
+}
+
+/// Projects when the work of the day may end, if the last entry of the day is open until `now`.
+/// Only the maximal work per day, the break rules and the minimal break of the config are
+/// considered. Returns `None` if there are no entries yet.
+pub fn project_leave_time(
+    day: &work_day::Day,
+    key_classes: &work_day::KeyClasses,
+    now: &DateTime,
+    balance_before: chrono::Duration,
+    config: &compliance::ComplianceConfig,
+) -> Option<LeaveProjection> {
+    let last_entry = day.work_day.entries.last()?;
+    let on_break = !key_classes.is_work(&last_entry.key);
+    let day = day.with_open_entry_until(now);
+    let worked = day.worked_time(key_classes);
+    let pattern = compliance::work_pattern(&day, key_classes, config.min_break);
+    let breaks = if on_break {
+        // the running break counts, as the work is resumed after it
+        pattern.breaks + pattern.trailing_interruption
+    } else {
+        pattern.breaks
+    };
+    let required = day.required_time.required_time;
+    let (leave_at, missing_break) = leave_time(now, worked, breaks, on_break, required, config);
+    let balanced_target = required - balance_before;
+    let (balanced_leave_at, balanced_missing_break) =
+        leave_time(now, worked, breaks, on_break, balanced_target, config);
+    Some(LeaveProjection {
+        now: *now,
+        worked,
+        required,
+        duration_of_day: day.duration_of_day,
+        leave_at,
+        missing_break,
+        balance_before,
+        balanced_leave_at,
+        balanced_missing_break,
+        balanced_exceeds_max_daily_work: balanced_target > config.max_daily_work,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_day(txt: &str) -> work_day::Day {
+        let date = Date::from_ymd_opt(2018, 5, 9).unwrap();
+        let key_classes = work_day::KeyClasses::new(vec!["Pause".to_string()], Vec::new());
+        work_day::Day {
+            duration_of_day: chrono::Duration::hours(8),
+            required_time: required_time::RequiredTime {
+                date,
+                day_type: required_time::DayType::WorkDay,
+                required_time: chrono::Duration::hours(8),
+                line_nr: 0,
+            },
+            work_day: work_day::WorkDay::parse(
+                &mut std::io::BufReader::new(txt.as_bytes()),
+                Some(date),
+                false,
+                true,
+                &key_classes,
+                "tst_file",
+            )
+            .unwrap(),
+        }
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime {
+        Date::from_ymd_opt(2018, 5, 9)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_leave_time_without_break() {
+        let day = make_day(
+            r"-- 2018-05-09 Mi 08:00 -- Foo
+-- 2018-05-09 Mi 10:00 -- Bar",
+        );
+        let projection = project_leave_time(
+            &day,
+            &work_day::KeyClasses::default(),
+            &at(12, 0),
+            chrono::Duration::hours(2),
+            &compliance::ComplianceConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(projection.worked, chrono::Duration::hours(4));
+        // 4h of work and a break of 30m are missing
+        assert_eq!(projection.missing_break, chrono::Duration::minutes(30));
+        assert_eq!(projection.leave_at, at(16, 30));
+        // with the balance of 2h only 2h of work are missing and 6h of work require no break
+        assert_eq!(projection.balanced_missing_break, chrono::Duration::zero());
+        assert_eq!(projection.balanced_leave_at, at(14, 0));
+    }
+
+    #[test]
+    fn test_leave_time_on_break() {
+        let day = make_day(
+            r"-- 2018-05-09 Mi 08:00 -- Foo
+-- 2018-05-09 Mi 12:00 -- Pause",
+        );
+        let config = compliance::ComplianceConfig::default();
+        // the running break of 10m is extended by 20m
+        let projection = project_leave_time(
+            &day,
+            &work_day::KeyClasses::default(),
+            &at(12, 10),
+            chrono::Duration::zero(),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(projection.worked, chrono::Duration::hours(4));
+        assert_eq!(projection.missing_break, chrono::Duration::minutes(20));
+        assert_eq!(projection.leave_at, at(16, 30));
+
+        // a deficit of 1h requires 9h of work and a break of 30m
+        let projection = project_leave_time(
+            &day,
+            &work_day::KeyClasses::default(),
+            &at(12, 10),
+            chrono::Duration::hours(-1),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(
+            projection.balanced_missing_break,
+            chrono::Duration::minutes(20)
+        );
+        assert_eq!(projection.balanced_leave_at, at(17, 30));
+        assert!(!projection.balanced_exceeds_max_daily_work);
+        // with a balance of 5h the work is already done
+        let projection = project_leave_time(
+            &day,
+            &work_day::KeyClasses::default(),
+            &at(12, 10),
+            chrono::Duration::hours(5),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(projection.balanced_leave_at, at(12, 10));
+    }
+
+    #[test]
+    fn test_leave_time_capped_by_max_daily_work() {
+        let day = make_day(
+            r"-- 2018-05-09 Mi 08:00 -- Foo
+-- 2018-05-09 Mi 12:00 -- Pause",
+        );
+        // a deficit of 5h would require 13h of work, but at most 10h with a break of 45m are allowed
+        let projection = project_leave_time(
+            &day,
+            &work_day::KeyClasses::default(),
+            &at(12, 10),
+            chrono::Duration::hours(-5),
+            &compliance::ComplianceConfig::default(),
+        )
+        .unwrap();
+        assert!(projection.balanced_exceeds_max_daily_work);
+        assert_eq!(
+            projection.balanced_missing_break,
+            chrono::Duration::minutes(35)
+        );
+        assert_eq!(projection.balanced_leave_at, at(18, 45));
+        assert_eq!(projection.leave_at, at(16, 30));
+    }
+
+    #[test]
+    fn test_leave_time_without_entries() {
+        let mut day = make_day(
+            r"-- 2018-05-09 Mi 08:00 -- Foo
+-- 2018-05-09 Mi 12:00 -- Pause",
+        );
+        day.work_day.entries.clear();
+        assert_eq!(
+            project_leave_time(
+                &day,
+                &work_day::KeyClasses::default(),
+                &at(12, 0),
+                chrono::Duration::zero(),
+                &compliance::ComplianceConfig::default()
+            ),
+            None
+        );
+    }
+}
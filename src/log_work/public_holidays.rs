@@ -0,0 +1,469 @@
+use self::chrono::Datelike;
+use super::*;
+
+/// The regions with a built-in calendar of public holidays. 'DE' only contains the nationwide
+/// holidays of Germany.
+const REGIONS: [&str; 18] = [
+    "DE", "DE-BB", "DE-BE", "DE-BW", "DE-BY", "DE-HB", "DE-HE", "DE-HH", "DE-MV", "DE-NI", "DE-NW",
+    "DE-RP", "DE-SH", "DE-SL", "DE-SN", "DE-ST", "DE-TH", "AT",
+];
+
+/// A region with a calendar of public holidays, e.g. 'DE-BY' or 'AT'
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Region {
+    code: &'static str,
+}
+
+impl Region {
+    fn matches(&self, regions: &[&str]) -> bool {
+        regions
+            .iter()
+            .any(|region| *region == self.code || (*region == "DE" && self.code.starts_with("DE-")))
+    }
+}
+
+impl std::str::FromStr for Region {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Region> {
+        REGIONS
+            .iter()
+            .find(|code| code.eq_ignore_ascii_case(s.trim()))
+            .map(|code| Region { code })
+            .ok_or_else(|| {
+                Error::CommandLine(format!(
+                    "Unknown holiday region '{}', expected one of {}",
+                    s,
+                    REGIONS.join(", ")
+                ))
+            })
+    }
+}
+
+enum HolidayDate {
+    Fixed {
+        month: u32,
+        day: u32,
+    },
+    /// The number of days after easter sunday
+    Easter(i64),
+    /// The wednesday before the 23rd of november
+    BussUndBettag,
+}
+
+struct Rule {
+    name: &'static str,
+    date: HolidayDate,
+    regions: &'static [&'static str],
+    /// The years in which the holiday exists
+    years: std::ops::RangeInclusive<i32>,
+}
+
+const ALL_YEARS: std::ops::RangeInclusive<i32> = i32::MIN..=i32::MAX;
+
+/// Holidays which are only observed in some municipalities of a state (e.g. 'Mariä Himmelfahrt' in
+/// Bavaria) are missing and have to be added to the holidays file.
+const RULES: [Rule; 27] = [
+    Rule {
+        name: "Neujahr",
+        date: HolidayDate::Fixed { month: 1, day: 1 },
+        regions: &["DE", "AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Heilige Drei Könige",
+        date: HolidayDate::Fixed { month: 1, day: 6 },
+        regions: &["DE-BW", "DE-BY", "DE-ST", "AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Internationaler Frauentag",
+        date: HolidayDate::Fixed { month: 3, day: 8 },
+        regions: &["DE-BE"],
+        years: 2019..=i32::MAX,
+    },
+    Rule {
+        name: "Internationaler Frauentag",
+        date: HolidayDate::Fixed { month: 3, day: 8 },
+        regions: &["DE-MV"],
+        years: 2023..=i32::MAX,
+    },
+    Rule {
+        name: "Karfreitag",
+        date: HolidayDate::Easter(-2),
+        regions: &["DE"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Ostermontag",
+        date: HolidayDate::Easter(1),
+        regions: &["DE", "AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Tag der Arbeit",
+        date: HolidayDate::Fixed { month: 5, day: 1 },
+        regions: &["DE"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Staatsfeiertag",
+        date: HolidayDate::Fixed { month: 5, day: 1 },
+        regions: &["AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Christi Himmelfahrt",
+        date: HolidayDate::Easter(39),
+        regions: &["DE", "AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Pfingstmontag",
+        date: HolidayDate::Easter(50),
+        regions: &["DE", "AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Fronleichnam",
+        date: HolidayDate::Easter(60),
+        regions: &["DE-BW", "DE-BY", "DE-HE", "DE-NW", "DE-RP", "DE-SL", "AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Mariä Himmelfahrt",
+        date: HolidayDate::Fixed { month: 8, day: 15 },
+        regions: &["DE-SL", "AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Weltkindertag",
+        date: HolidayDate::Fixed { month: 9, day: 20 },
+        regions: &["DE-TH"],
+        years: 2019..=i32::MAX,
+    },
+    Rule {
+        name: "Tag der Deutschen Einheit",
+        date: HolidayDate::Fixed { month: 10, day: 3 },
+        regions: &["DE"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Nationalfeiertag",
+        date: HolidayDate::Fixed { month: 10, day: 26 },
+        regions: &["AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Reformationstag",
+        date: HolidayDate::Fixed { month: 10, day: 31 },
+        regions: &["DE-BB", "DE-MV", "DE-SN", "DE-ST", "DE-TH"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Reformationstag",
+        date: HolidayDate::Fixed { month: 10, day: 31 },
+        regions: &["DE-HB", "DE-HH", "DE-NI", "DE-SH"],
+        years: 2018..=i32::MAX,
+    },
+    Rule {
+        // the 500th anniversary of the reformation was a nationwide holiday
+        name: "Reformationstag",
+        date: HolidayDate::Fixed { month: 10, day: 31 },
+        regions: &[
+            "DE-BE", "DE-BW", "DE-BY", "DE-HB", "DE-HE", "DE-HH", "DE-NI", "DE-NW", "DE-RP",
+            "DE-SH", "DE-SL",
+        ],
+        years: 2017..=2017,
+    },
+    Rule {
+        name: "Allerheiligen",
+        date: HolidayDate::Fixed { month: 11, day: 1 },
+        regions: &["DE-BW", "DE-BY", "DE-NW", "DE-RP", "DE-SL", "AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Buß- und Bettag",
+        date: HolidayDate::BussUndBettag,
+        regions: &["DE-SN"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Mariä Empfängnis",
+        date: HolidayDate::Fixed { month: 12, day: 8 },
+        regions: &["AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "1. Weihnachtsfeiertag",
+        date: HolidayDate::Fixed { month: 12, day: 25 },
+        regions: &["DE"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "2. Weihnachtsfeiertag",
+        date: HolidayDate::Fixed { month: 12, day: 26 },
+        regions: &["DE"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Christtag",
+        date: HolidayDate::Fixed { month: 12, day: 25 },
+        regions: &["AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Stefanitag",
+        date: HolidayDate::Fixed { month: 12, day: 26 },
+        regions: &["AT"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Ostersonntag",
+        date: HolidayDate::Easter(0),
+        regions: &["DE-BB"],
+        years: ALL_YEARS,
+    },
+    Rule {
+        name: "Pfingstsonntag",
+        date: HolidayDate::Easter(49),
+        regions: &["DE-BB"],
+        years: ALL_YEARS,
+    },
+];
+
+/// Computes easter sunday of the gregorian calendar (anonymous gregorian algorithm)
+pub fn easter_sunday(year: i32) -> Result<Date> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    Date::from_ymd_opt(year, month as u32, day as u32).ok_or(Error::ParseDay)
+}
+
+impl HolidayDate {
+    fn in_year(&self, year: i32) -> Result<Date> {
+        match *self {
+            HolidayDate::Fixed { month, day } => {
+                Date::from_ymd_opt(year, month, day).ok_or(Error::ParseDay)
+            }
+            HolidayDate::Easter(offset) => {
+                Ok(easter_sunday(year)? + chrono::Duration::days(offset))
+            }
+            HolidayDate::BussUndBettag => {
+                let nov_22 = Date::from_ymd_opt(year, 11, 22).ok_or(Error::ParseDay)?;
+                let days_since_wednesday = (nov_22.weekday().num_days_from_monday() + 5) % 7;
+                Ok(nov_22 - chrono::Duration::days(days_since_wednesday as i64))
+            }
+        }
+    }
+}
+
+/// The public holidays of the region between start_date and end_date (both inclusive) sorted by
+/// date. They are meant to be consolidated together with the entries of the holidays file.
+pub fn public_holidays(
+    region: &Region,
+    start_date: &Date,
+    end_date: &Date,
+) -> Result<Vec<required_time::DayTypeEntry>> {
+    let mut ret = Vec::new();
+    for year in start_date.year()..=end_date.year() {
+        for rule in RULES.iter() {
+            if !rule.years.contains(&year) || !region.matches(rule.regions) {
+                continue;
+            }
+            let date = rule.date.in_year(year)?;
+            if date < *start_date || date > *end_date {
+                continue;
+            }
+            ret.push(required_time::DayTypeEntry::new(
+                date,
+                required_time::DayType::Holiday {
+                    name: rule.name.to_string(),
+                },
+                false,
+                "",
+                0,
+            ));
+        }
+    }
+    ret.sort_by_key(|entry| entry.date());
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holidays_of_year(region: &str, year: i32) -> Vec<(String, String)> {
+        let start_date = Date::from_ymd_opt(year, 1, 1).unwrap();
+        let end_date = Date::from_ymd_opt(year, 12, 31).unwrap();
+        let entries = public_holidays(&region.parse().unwrap(), &start_date, &end_date).unwrap();
+        required_time::consolidate_required_time(
+            &entries,
+            &start_date,
+            &end_date,
+            &schedule::Schedule::from_duration_of_day(chrono::Duration::hours(8)).into(),
+        )
+        .unwrap()
+        .iter()
+        .filter_map(|required_time| match required_time.day_type {
+            required_time::DayType::Holiday { ref name } => {
+                Some((required_time.date.format("%m-%d").to_string(), name.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_easter_sunday() {
+        let easter_sundays: Vec<_> = [2018, 2019, 2024, 2025, 2038]
+            .iter()
+            .map(|year| easter_sunday(*year).unwrap().format("%F").to_string())
+            .collect();
+        assert_eq!(
+            easter_sundays,
+            vec![
+                "2018-04-01",
+                "2019-04-21",
+                "2024-03-31",
+                "2025-04-20",
+                "2038-04-25"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_public_holidays_bavaria() {
+        let holidays = holidays_of_year("DE-BY", 2019);
+        let expected: Vec<(String, String)> = [
+            ("01-01", "Neujahr"),
+            ("01-06", "Heilige Drei Könige"),
+            ("04-19", "Karfreitag"),
+            ("04-22", "Ostermontag"),
+            ("05-01", "Tag der Arbeit"),
+            ("05-30", "Christi Himmelfahrt"),
+            ("06-10", "Pfingstmontag"),
+            ("06-20", "Fronleichnam"),
+            ("10-03", "Tag der Deutschen Einheit"),
+            ("11-01", "Allerheiligen"),
+            ("12-25", "1. Weihnachtsfeiertag"),
+            ("12-26", "2. Weihnachtsfeiertag"),
+        ]
+        .iter()
+        .map(|(date, name)| (date.to_string(), name.to_string()))
+        .collect();
+        assert_eq!(holidays, expected);
+    }
+
+    #[test]
+    fn test_public_holidays_austria() {
+        let holidays = holidays_of_year("AT", 2024);
+        assert_eq!(holidays.len(), 13);
+        assert!(!holidays.iter().any(|(_, name)| name == "Karfreitag"));
+        assert!(holidays.contains(&("05-30".to_string(), "Fronleichnam".to_string())));
+        assert!(holidays.contains(&("12-08".to_string(), "Mariä Empfängnis".to_string())));
+    }
+
+    #[test]
+    fn test_public_holidays_depending_on_year() {
+        let has_holiday = |region: &str, year: i32, date: &str| {
+            holidays_of_year(region, year)
+                .iter()
+                .any(|(d, _)| d == date)
+        };
+        assert!(has_holiday("DE-BW", 2017, "10-31"));
+        assert!(!has_holiday("DE-BW", 2018, "10-31"));
+        assert!(!has_holiday("DE-NI", 2016, "10-31"));
+        assert!(has_holiday("DE-NI", 2018, "10-31"));
+        assert!(!has_holiday("DE-BE", 2018, "03-08"));
+        assert!(has_holiday("DE-BE", 2019, "03-08"));
+        assert!(has_holiday("DE-SN", 2018, "11-21"));
+        assert!(has_holiday("DE-SN", 2023, "11-22"));
+        assert!(!has_holiday("DE", 2018, "11-21"));
+    }
+
+    #[test]
+    fn test_public_holidays_in_range() {
+        let holidays = public_holidays(
+            &"de-bw".parse().unwrap(),
+            &Date::from_ymd_opt(2018, 12, 24).unwrap(),
+            &Date::from_ymd_opt(2019, 1, 6).unwrap(),
+        )
+        .unwrap();
+        let dates: Vec<_> = holidays
+            .iter()
+            .map(|entry| entry.date().format("%F").to_string())
+            .collect();
+        assert_eq!(
+            dates,
+            vec!["2018-12-25", "2018-12-26", "2019-01-01", "2019-01-06"]
+        );
+    }
+
+    #[test]
+    fn test_consolidate_with_holidays_file() {
+        let start_date = Date::from_ymd_opt(2019, 4, 18).unwrap();
+        let end_date = Date::from_ymd_opt(2019, 4, 23).unwrap();
+        let schedules: schedule::ScheduleSeries =
+            schedule::Schedule::from_duration_of_day(chrono::Duration::hours(8)).into();
+        let mut entries = required_time::parse_required_time(
+            &mut std::io::BufReader::new(
+                "2019-04-19 -- F Good Friday\n2019-04-18--2019-04-23 -- U Easter".as_bytes(),
+            ),
+            "tst_file",
+        )
+        .unwrap();
+        entries.append(
+            &mut public_holidays(&"DE-HE".parse().unwrap(), &start_date, &end_date).unwrap(),
+        );
+        let result =
+            required_time::consolidate_required_time(&entries, &start_date, &end_date, &schedules)
+                .unwrap();
+        let day_types: Vec<_> = result
+            .iter()
+            .map(|required_time| required_time.day_type.to_string())
+            .collect();
+        assert_eq!(
+            day_types,
+            vec![
+                "Vacation(Easter)",
+                "Holiday(Good)",
+                "Vacation(Easter)",
+                "Vacation(Easter)",
+                "Holiday(Ostermontag)",
+                "Vacation(Easter)",
+            ]
+        );
+
+        // like with holidays of the file a single vacation day on a public holiday is rejected
+        let mut entries = required_time::parse_required_time(
+            &mut std::io::BufReader::new("2019-04-22 -- U Easter".as_bytes()),
+            "tst_file",
+        )
+        .unwrap();
+        entries.append(
+            &mut public_holidays(&"DE-HE".parse().unwrap(), &start_date, &end_date).unwrap(),
+        );
+        let result =
+            required_time::consolidate_required_time(&entries, &start_date, &end_date, &schedules);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_unknown_region() {
+        assert!("DE-XX".parse::<Region>().is_err());
+        assert!("".parse::<Region>().is_err());
+    }
+}
@@ -0,0 +1,375 @@
+use self::chrono::Datelike;
+use super::work_day;
+use super::*;
+
+/// The period by which the days of a report are grouped
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Period {
+    Week,
+    Month,
+    Year,
+}
+
+impl std::str::FromStr for Period {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Period> {
+        match s {
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            "year" => Ok(Period::Year),
+            _ => Err(Error::CommandLine(format!(
+                "Unknown period '{}', expected one of 'week', 'month' or 'year'",
+                s
+            ))),
+        }
+    }
+}
+
+impl Period {
+    /// The name of the period the date belongs to (e.g. '2018-W19', '2018-05' or '2018')
+    pub fn label(&self, date: &Date) -> String {
+        match *self {
+            Period::Week => {
+                let iso_week = date.iso_week();
+                format!("{}-W{:02}", iso_week.year(), iso_week.week())
+            }
+            Period::Month => date.format("%Y-%m").to_string(),
+            Period::Year => date.format("%Y").to_string(),
+        }
+    }
+}
+
+/// The format in which the report is written
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    /// One row per entry
+    Csv,
+    /// Like `Csv` but separated by tabs
+    Tsv,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<OutputFormat> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            _ => Err(Error::CommandLine(format!(
+                "Unknown format '{}', expected one of 'text', 'json', 'csv' or 'tsv'",
+                s
+            ))),
+        }
+    }
+}
+
+/// The aggregated times of all days belonging to one period
+pub struct PeriodSummary<'a> {
+    pub label: String,
+    pub days: Vec<&'a work_day::Day>,
+    pub duration_of_day: chrono::Duration,
+    pub key_classes: &'a work_day::KeyClasses,
+    /// The balance of the flextime account after the last day of the period, if it was booked
+    pub balance: Option<chrono::Duration>,
+}
+
+impl<'a> PeriodSummary<'a> {
+    pub fn compute_summary(&self) -> work_day::Summary {
+        let mut summary = work_day::Summary::new();
+        for day in &self.days {
+            work_day::WorkDay::merge_summaries_right_into_left(
+                &mut summary,
+                &day.work_day.compute_summary(),
+            );
+        }
+        summary
+    }
+
+    pub fn required_time(&self) -> chrono::Duration {
+        self.days
+            .iter()
+            .fold(chrono::Duration::hours(0), |sum, day| {
+                sum + day.required_time.required_time
+            })
+    }
+
+    pub fn worked_time(&self) -> chrono::Duration {
+        self.days
+            .iter()
+            .fold(chrono::Duration::hours(0), |sum, day| {
+                sum + day.worked_time(self.key_classes)
+            })
+    }
+
+    /// Sets the balance of the period by booking all of its days
+    pub fn book_days(&mut self, flextime_account: &mut balance::FlextimeAccount) {
+        for day in &self.days {
+            if let Some(balance) = flextime_account.book_day(day, self.key_classes) {
+                self.balance = Some(balance);
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> JsonPeriod {
+        JsonPeriod {
+            label: self.label.clone(),
+            first_date: self.days.first().map(|day| day.required_time.date),
+            last_date: self.days.last().map(|day| day.required_time.date),
+            summary: self.to_json_summary(),
+        }
+    }
+
+    pub fn to_json_summary(&self) -> JsonSummary {
+        JsonSummary {
+            keys: self.compute_summary(),
+            required_time: self.required_time(),
+            worked_time: self.worked_time(),
+            balance: self.balance,
+        }
+    }
+}
+
+impl<'a> std::fmt::Display for PeriodSummary<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let duration_of_day = self.duration_of_day;
+        match (self.days.first(), self.days.last()) {
+            (Some(first), Some(last)) => writeln!(
+                f,
+                "= {} ({} -- {}):",
+                self.label,
+                first.required_time.date.format("%F"),
+                last.required_time.date.format("%F")
+            )?,
+            _ => writeln!(f, "= {}:", self.label)?,
+        }
+        let alias_sources = alias::AliasSources::from_entries(
+            self.days.iter().flat_map(|day| day.work_day.entries.iter()),
+        );
+        for (key, duration) in self.compute_summary().iter() {
+            writeln!(
+                f,
+                "{:20}: {:>19}{}",
+                key,
+                util::WorkDuration {
+                    duration_of_day,
+                    duration: *duration
+                },
+                alias_sources.describe(key)
+            )?;
+        }
+        let required_time = self.required_time();
+        let worked_time = self.worked_time();
+        writeln!(
+            f,
+            "{:20}: {:>19}",
+            " == Required ==",
+            util::WorkDuration {
+                duration_of_day,
+                duration: required_time
+            }
+        )?;
+        writeln!(
+            f,
+            "{:20}: {:>19}",
+            " == Total ==",
+            util::WorkDuration {
+                duration_of_day,
+                duration: worked_time
+            }
+        )?;
+        writeln!(
+            f,
+            "{:20}: {:>19}",
+            " == Delta ==",
+            util::WorkDuration {
+                duration_of_day,
+                duration: worked_time - required_time
+            }
+        )?;
+        if let Some(balance) = self.balance {
+            writeln!(
+                f,
+                "{:20}: {:>19}",
+                " == Balance ==",
+                util::WorkDuration {
+                    duration_of_day,
+                    duration: balance
+                }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A day together with the balance of the flextime account after it
+#[derive(serde::Serialize)]
+pub struct JsonDay<'a> {
+    #[serde(flatten)]
+    pub day: &'a work_day::Day,
+    #[serde(serialize_with = "util::serialize_opt_minutes")]
+    pub balance: Option<chrono::Duration>,
+}
+
+/// The aggregated times of a number of days (all durations are given in minutes)
+#[derive(serde::Serialize)]
+pub struct JsonSummary {
+    #[serde(serialize_with = "util::serialize_summary_minutes")]
+    pub keys: work_day::Summary,
+    #[serde(serialize_with = "util::serialize_minutes")]
+    pub required_time: chrono::Duration,
+    #[serde(serialize_with = "util::serialize_minutes")]
+    pub worked_time: chrono::Duration,
+    #[serde(serialize_with = "util::serialize_opt_minutes")]
+    pub balance: Option<chrono::Duration>,
+}
+
+#[derive(serde::Serialize)]
+pub struct JsonPeriod {
+    pub label: String,
+    pub first_date: Option<Date>,
+    pub last_date: Option<Date>,
+    pub summary: JsonSummary,
+}
+
+#[derive(serde::Serialize)]
+pub struct JsonReport<'a> {
+    pub days: Vec<JsonDay<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub periods: Vec<JsonPeriod>,
+    pub summary: JsonSummary,
+}
+
+/// Splits the (sorted) days into consecutive groups belonging to the same period
+pub fn group_days<'a>(
+    days: &'a [work_day::Day],
+    period: Period,
+    duration_of_day: chrono::Duration,
+    key_classes: &'a work_day::KeyClasses,
+) -> Vec<PeriodSummary<'a>> {
+    let mut ret: Vec<PeriodSummary<'a>> = Vec::new();
+    for day in days {
+        let label = period.label(&day.required_time.date);
+        match ret.last_mut() {
+            Some(group) if group.label == label => group.days.push(day),
+            _ => ret.push(PeriodSummary {
+                label,
+                days: vec![day],
+                duration_of_day,
+                key_classes,
+                balance: None,
+            }),
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_day(date: Date) -> work_day::Day {
+        work_day::Day {
+            duration_of_day: chrono::Duration::hours(8),
+            required_time: required_time::RequiredTime {
+                date,
+                day_type: required_time::DayType::WorkDay,
+                required_time: chrono::Duration::hours(8),
+                line_nr: 0,
+            },
+            work_day: work_day::WorkDay {
+                date,
+                entries: Vec::new(),
+                additional_text: String::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_period_label() {
+        let date = Date::from_ymd_opt(2021, 1, 3).unwrap();
+        assert_eq!(Period::Week.label(&date), "2020-W53");
+        assert_eq!(Period::Month.label(&date), "2021-01");
+        assert_eq!(Period::Year.label(&date), "2021");
+    }
+
+    #[test]
+    fn test_group_days_by_week() {
+        let days: Vec<_> = [(2018, 5, 5), (2018, 5, 6), (2018, 5, 7), (2018, 5, 14)]
+            .iter()
+            .map(|&(y, m, d)| make_day(Date::from_ymd_opt(y, m, d).unwrap()))
+            .collect();
+        let key_classes = work_day::KeyClasses::default();
+        let groups = group_days(
+            &days,
+            Period::Week,
+            chrono::Duration::hours(8),
+            &key_classes,
+        );
+        let groups: Vec<_> = groups
+            .iter()
+            .map(|group| (group.label.as_str(), group.days.len()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![("2018-W18", 2), ("2018-W19", 1), ("2018-W20", 1)]
+        );
+    }
+
+    #[test]
+    fn test_json_report() {
+        let days = [make_day(Date::from_ymd_opt(2018, 5, 7).unwrap())];
+        let mut all_days = PeriodSummary {
+            label: String::new(),
+            days: days.iter().collect(),
+            duration_of_day: chrono::Duration::hours(8),
+            key_classes: &work_day::KeyClasses::default(),
+            balance: None,
+        };
+        all_days.book_days(&mut balance::FlextimeAccount::new(
+            None,
+            chrono::Duration::hours(0),
+        ));
+        let report = JsonReport {
+            days: vec![JsonDay {
+                day: &days[0],
+                balance: all_days.balance,
+            }],
+            periods: Vec::new(),
+            summary: all_days.to_json_summary(),
+        };
+        assert_eq!(
+            serde_json::to_string(&report).unwrap(),
+            concat!(
+                r#"{"days":[{"duration_of_day":480,"required_time":{"date":"2018-05-07","#,
+                r#""day_type":{"type":"WorkDay"},"required_time":480},"#,
+                r#""work_day":{"date":"2018-05-07","entries":[],"additional_text":""},"#,
+                r#""balance":-480}],"#,
+                r#""summary":{"keys":{},"required_time":480,"worked_time":0,"balance":-480}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_group_required_time() {
+        let days: Vec<_> = [(2018, 5, 30), (2018, 5, 31), (2018, 6, 1)]
+            .iter()
+            .map(|&(y, m, d)| make_day(Date::from_ymd_opt(y, m, d).unwrap()))
+            .collect();
+        let key_classes = work_day::KeyClasses::default();
+        let groups = group_days(
+            &days,
+            Period::Month,
+            chrono::Duration::hours(8),
+            &key_classes,
+        );
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].required_time(), chrono::Duration::hours(16));
+        assert_eq!(groups[0].worked_time(), chrono::Duration::hours(0));
+        assert_eq!(groups[1].required_time(), chrono::Duration::hours(8));
+    }
+}
@@ -1,12 +1,12 @@
 extern crate chrono;
 extern crate regex;
 
-use self::chrono::Datelike;
 use self::util;
 use super::*;
 use std;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize)]
+#[serde(tag = "type")]
 pub enum DayType {
     WorkDay,                           // A - Arbeitstag
     JobTravel { description: String }, // D - Dienstreise
@@ -40,19 +40,39 @@ impl DayType {
     }
 }
 
+impl DayType {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            DayType::WorkDay => "WorkDay",
+            DayType::OvertimeReduction { description: _ } => "OvertimeReduction",
+            DayType::WeekEnd => "WeekEnd",
+            DayType::JobTravel { description: _ } => "JobTravel",
+            DayType::Sick { description: _ } => "Sick",
+            DayType::Holiday { name: _ } => "Holiday",
+            DayType::Vacation { description: _ } => "Vacation",
+            DayType::VacationHalfDay { description: _ } => "VacationHalfDay",
+        }
+    }
+
+    /// The description (or the name of a holiday), if the day type has one
+    pub fn description(&self) -> Option<&str> {
+        match *self {
+            DayType::WorkDay | DayType::WeekEnd => None,
+            DayType::OvertimeReduction { ref description }
+            | DayType::JobTravel { ref description }
+            | DayType::Sick { ref description }
+            | DayType::Vacation { ref description }
+            | DayType::VacationHalfDay { ref description } => Some(description),
+            DayType::Holiday { ref name } => Some(name),
+        }
+    }
+}
+
 impl std::fmt::Display for DayType {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match *self {
-            DayType::WorkDay => write!(f, "WorkDay"),
-            DayType::OvertimeReduction { description: ref s } => {
-                write!(f, "OvertimeReduction({})", s)
-            }
-            DayType::WeekEnd => write!(f, "WeekEnd"),
-            DayType::JobTravel { description: ref s } => write!(f, "JobTravel({})", s),
-            DayType::Sick { description: ref s } => write!(f, "Sick({})", s),
-            DayType::Holiday { name: ref s } => write!(f, "Holiday({})", s),
-            DayType::Vacation { description: ref s } => write!(f, "Vacation({})", s),
-            DayType::VacationHalfDay { description: ref s } => write!(f, "VacationHalfDay({})", s),
+        match self.description() {
+            Some(description) => write!(f, "{}({})", self.name(), description),
+            None => write!(f, "{}", self.name()),
         }
     }
 }
@@ -62,9 +82,50 @@ pub struct DayTypeEntry {
     date: Date,
     day_type: DayType,
     given_as_range: bool,
+    file: String,
     line_nr: u32,
 }
 
+impl DayTypeEntry {
+    /// Entries of iCalendar files use the number of the event as line_nr, entries from other
+    /// sources than files an empty file and a line_nr of 0
+    pub fn new(
+        date: Date,
+        day_type: DayType,
+        given_as_range: bool,
+        file: &str,
+        line_nr: u32,
+    ) -> DayTypeEntry {
+        DayTypeEntry {
+            date,
+            day_type,
+            given_as_range,
+            file: file.to_string(),
+            line_nr,
+        }
+    }
+
+    pub fn date(&self) -> Date {
+        self.date
+    }
+
+    pub fn day_type(&self) -> &DayType {
+        &self.day_type
+    }
+
+    pub fn given_as_range(&self) -> bool {
+        self.given_as_range
+    }
+
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    pub fn line_nr(&self) -> u32 {
+        self.line_nr
+    }
+}
+
 fn get_day_type_description(c: &regex::Captures) -> String {
     if c.get(4).is_some() {
         return c[4].to_string();
@@ -86,7 +147,7 @@ fn check_day_types(orig: &DayTypeEntry, new_entry: &DayTypeEntry) -> Result<()>
             DayType::JobTravel { description: _ } => (),
             _ => {
                 return Err(Error::DuplicateDate {
-                    file: "".to_string(), /*orig.file.clone()*/
+                    file: orig.file.clone(),
                     line_nr: orig.line_nr,
                 });
             }
@@ -99,7 +160,7 @@ fn check_day_types(orig: &DayTypeEntry, new_entry: &DayTypeEntry) -> Result<()>
             DayType::JobTravel { description: _ } => (),
             _ => {
                 return Err(Error::DuplicateDate {
-                    file: "".to_string(), /*new_entry.file.clone()*/
+                    file: new_entry.file.clone(),
                     line_nr: new_entry.line_nr,
                 });
             }
@@ -108,11 +169,13 @@ fn check_day_types(orig: &DayTypeEntry, new_entry: &DayTypeEntry) -> Result<()>
     Ok(())
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize)]
 pub struct RequiredTime {
     pub date: Date,
     pub day_type: DayType,
+    #[serde(serialize_with = "util::serialize_minutes")]
     pub required_time: chrono::Duration,
+    #[serde(skip)]
     pub line_nr: u32,
 }
 
@@ -131,7 +194,7 @@ pub fn consolidate_required_time(
     raw_entries: &Vec<DayTypeEntry>,
     start_date: &Date,
     end_date: &Date,
-    duration_of_day: &chrono::Duration,
+    schedules: &schedule::ScheduleSeries,
 ) -> Result<Vec<RequiredTime>> {
     let mut map: std::collections::BTreeMap<Date, DayTypeEntry> = std::collections::BTreeMap::new();
     for raw_entry in raw_entries {
@@ -153,13 +216,15 @@ pub fn consolidate_required_time(
     let mut ret: Vec<RequiredTime> = Vec::new();
     let mut curr_date = *start_date;
     while curr_date <= *end_date {
+        let schedule = schedules.at(&curr_date);
         match map.get(&curr_date) {
             Some(day_type_entry) => {
                 check_day_types(
                     &DayTypeEntry {
                         date: curr_date,
-                        day_type: compute_day_type(&curr_date),
+                        day_type: compute_day_type(&curr_date, schedule),
                         given_as_range: true,
+                        file: String::new(),
                         line_nr: 0,
                     },
                     day_type_entry,
@@ -170,7 +235,7 @@ pub fn consolidate_required_time(
                     required_time: compute_required_time(
                         &curr_date,
                         &day_type_entry.day_type,
-                        duration_of_day,
+                        schedule,
                     ),
                     line_nr: day_type_entry.line_nr,
                 });
@@ -178,31 +243,29 @@ pub fn consolidate_required_time(
             None => {
                 ret.push(RequiredTime {
                     date: curr_date,
-                    day_type: compute_day_type(&curr_date),
-                    required_time: compute_simple_required_time(&curr_date, duration_of_day),
+                    day_type: compute_day_type(&curr_date, schedule),
+                    required_time: compute_simple_required_time(&curr_date, schedule),
                     line_nr: 0,
                 });
             }
         }
-        curr_date = curr_date.succ_opt().ok_or_else(|| Error::ParseDay)?;
+        curr_date = curr_date.succ_opt().ok_or(Error::ParseDay)?;
     }
     Ok(ret)
 }
 
-fn compute_day_type(date: &Date) -> DayType {
-    if date.weekday().num_days_from_monday() <= 4 {
+/// Days without required time according to the schedule are treated like weekends
+fn compute_day_type(date: &Date, schedule: &schedule::Schedule) -> DayType {
+    if schedule.is_work_day(date) {
         DayType::WorkDay
     } else {
         DayType::WeekEnd
     }
 }
 
-fn compute_simple_required_time(
-    date: &Date,
-    duration_of_day: &chrono::Duration,
-) -> chrono::Duration {
-    match compute_day_type(date) {
-        DayType::WorkDay => *duration_of_day,
+fn compute_simple_required_time(date: &Date, schedule: &schedule::Schedule) -> chrono::Duration {
+    match compute_day_type(date, schedule) {
+        DayType::WorkDay => schedule.required_time(date),
         DayType::WeekEnd => chrono::Duration::hours(0),
         _ => {
             panic!("Error in compute_day_type()");
@@ -213,9 +276,9 @@ fn compute_simple_required_time(
 fn compute_required_time(
     date: &Date,
     day_type: &DayType,
-    duration_of_day: &chrono::Duration,
+    schedule: &schedule::Schedule,
 ) -> chrono::Duration {
-    if let DayType::WeekEnd = compute_day_type(date) {
+    if let DayType::WeekEnd = compute_day_type(date, schedule) {
         return chrono::Duration::hours(0);
     };
     match *day_type {
@@ -225,8 +288,8 @@ fn compute_required_time(
         DayType::Holiday { name: _ } | DayType::Vacation { description: _ } => {
             chrono::Duration::hours(0)
         }
-        DayType::VacationHalfDay { description: _ } => (*duration_of_day) / 2,
-        DayType::OvertimeReduction { description: _ } => *duration_of_day,
+        DayType::VacationHalfDay { description: _ } => schedule.required_time(date) / 2,
+        DayType::OvertimeReduction { description: _ } => schedule.required_time(date),
         DayType::WeekEnd | DayType::WorkDay => {
             panic!("illegal DayType: {:?}", *day_type);
         }
@@ -248,41 +311,32 @@ pub fn parse_required_time_file(file_name: &std::path::PathBuf) -> Result<Vec<Da
     Ok(ret)
 }
 
+impl DayType {
+    /// The day type of a letter of the holidays file (one of 'WKFUHÜ')
+    pub fn from_letter(letter: &str, description: String) -> Option<DayType> {
+        match letter {
+            "W" => Some(DayType::JobTravel { description }),
+            "K" => Some(DayType::Sick { description }),
+            "F" => Some(DayType::Holiday { name: description }),
+            "U" => Some(DayType::Vacation { description }),
+            "H" => Some(DayType::VacationHalfDay { description }),
+            "Ü" => Some(DayType::OvertimeReduction { description }),
+            _ => None,
+        }
+    }
+}
+
 fn day_type_from_str(s: &str, file_name: &str, line_nr: u32) -> Result<DayType> {
     lazy_static::lazy_static! {
         static ref RE: regex::Regex = regex::Regex::new(r"^([WKFUHÜ]) +((([^:]*):)|([^ ]*)).*$")
             .expect("Erronuous Regular Expression for holiday type parsing");
     }
-    match RE.captures(s) {
-        Some(c) => match &c[1] {
-            "W" => Ok(DayType::JobTravel {
-                description: get_day_type_description(&c),
-            }),
-            "K" => Ok(DayType::Sick {
-                description: get_day_type_description(&c),
-            }),
-            "F" => Ok(DayType::Holiday {
-                name: get_day_type_description(&c),
-            }),
-            "U" => Ok(DayType::Vacation {
-                description: get_day_type_description(&c),
-            }),
-            "H" => Ok(DayType::VacationHalfDay {
-                description: get_day_type_description(&c),
-            }),
-            "Ü" => Ok(DayType::OvertimeReduction {
-                description: get_day_type_description(&c),
-            }),
-            _ => Err(Error::ParseDayType {
-                file: file_name.to_string(),
-                line_nr,
-            }),
-        },
-        None => Err(Error::ParseDayType {
+    RE.captures(s)
+        .and_then(|c| DayType::from_letter(&c[1], get_day_type_description(&c)))
+        .ok_or_else(|| Error::ParseDayType {
             file: file_name.to_string(),
             line_nr,
-        }),
-    }
+        })
 }
 
 pub fn parse_required_time(
@@ -324,9 +378,10 @@ pub fn parse_required_time(
                     date: curr_day,
                     day_type: day_type.clone(),
                     given_as_range,
+                    file: file_name.to_string(),
                     line_nr,
                 });
-                curr_day = curr_day.succ_opt().ok_or_else(|| Error::ParseDay)?;
+                curr_day = curr_day.succ_opt().ok_or(Error::ParseDay)?;
             }
         }
     }
@@ -343,12 +398,13 @@ mod tests {
         let txt: &str = r"2018-05-04 -- W Mehrere Worte:";
         let expected = Ok(vec![DayTypeEntry {
             date: Date::from_ymd_opt(2018, 5, 4)
-                .ok_or_else(|| Error::ParseDay)
+                .ok_or(Error::ParseDay)
                 .unwrap(),
             day_type: DayType::JobTravel {
                 description: "Mehrere Worte".to_string(),
             },
             given_as_range: false,
+            file: "tst_file".to_string(),
             line_nr: 1,
         }]);
         do_test_parse_required_time(txt, expected);
@@ -364,6 +420,7 @@ mod tests {
                     description: "This".to_string(),
                 },
                 given_as_range: true,
+                file: "tst_file".to_string(),
                 line_nr: 1,
             },
             DayTypeEntry {
@@ -372,6 +429,7 @@ mod tests {
                     description: "This".to_string(),
                 },
                 given_as_range: true,
+                file: "tst_file".to_string(),
                 line_nr: 1,
             },
         ]);
@@ -388,6 +446,7 @@ mod tests {
                     description: "This is".to_string(),
                 },
                 given_as_range: true,
+                file: "tst_file".to_string(),
                 line_nr: 1,
             },
             DayTypeEntry {
@@ -396,6 +455,7 @@ mod tests {
                     description: "This is".to_string(),
                 },
                 given_as_range: true,
+                file: "tst_file".to_string(),
                 line_nr: 1,
             },
         ]);
@@ -414,6 +474,7 @@ mod tests {
                     name: "This is".to_string(),
                 },
                 given_as_range: false,
+                file: "tst_file".to_string(),
                 line_nr: 1,
             },
             DayTypeEntry {
@@ -422,6 +483,7 @@ mod tests {
                     description: "A".to_string(),
                 },
                 given_as_range: false,
+                file: "tst_file".to_string(),
                 line_nr: 2,
             },
             DayTypeEntry {
@@ -430,6 +492,7 @@ mod tests {
                     description: "Brückentag".to_string(),
                 },
                 given_as_range: false,
+                file: "tst_file".to_string(),
                 line_nr: 3,
             },
         ]);
@@ -451,6 +514,7 @@ bar
                     name: "This is".to_string(),
                 },
                 given_as_range: false,
+                file: "tst_file".to_string(),
                 line_nr: 2,
             },
             DayTypeEntry {
@@ -459,6 +523,7 @@ bar
                     description: "A".to_string(),
                 },
                 given_as_range: false,
+                file: "tst_file".to_string(),
                 line_nr: 4,
             },
         ]);
@@ -503,6 +568,7 @@ bar
                     name: "ho".to_string(),
                 },
                 given_as_range: false,
+                file: "holidays".to_string(),
                 line_nr: 1,
             },
             DayTypeEntry {
@@ -511,6 +577,7 @@ bar
                     description: "A".to_string(),
                 },
                 given_as_range: true,
+                file: "holidays".to_string(),
                 line_nr: 5,
             },
         ];
@@ -519,7 +586,7 @@ bar
             &special_required_times,
             &Date::from_ymd_opt(2018, 5, 3).unwrap(),
             &Date::from_ymd_opt(2018, 5, 6).unwrap(),
-            &full_day_duration,
+            &schedule::Schedule::from_duration_of_day(full_day_duration).into(),
         );
         let expected = Ok(vec![
             RequiredTime {
@@ -563,6 +630,7 @@ bar
                     name: "ho".to_string(),
                 },
                 given_as_range: false,
+                file: "holidays".to_string(),
                 line_nr: 1,
             },
             DayTypeEntry {
@@ -571,6 +639,7 @@ bar
                     description: "A".to_string(),
                 },
                 given_as_range: false,
+                file: "holidays".to_string(),
                 line_nr: 5,
             },
         ];
@@ -579,10 +648,10 @@ bar
             &special_required_times,
             &Date::from_ymd_opt(2018, 5, 3).unwrap(),
             &Date::from_ymd_opt(2018, 5, 5).unwrap(),
-            &full_day_duration,
+            &schedule::Schedule::from_duration_of_day(full_day_duration).into(),
         );
         let expected = Err(Error::DuplicateDate {
-            file: "".to_string(),
+            file: "holidays".to_string(),
             line_nr: 5,
         });
         assert_eq!(result, expected);
@@ -596,6 +665,7 @@ bar
                 description: "A".to_string(),
             },
             given_as_range: false,
+            file: "holidays".to_string(),
             line_nr: 5,
         }];
         let full_day_duration = chrono::Duration::hours(7) + chrono::Duration::minutes(42);
@@ -603,10 +673,10 @@ bar
             &special_required_times,
             &Date::from_ymd_opt(2018, 5, 3).unwrap(),
             &Date::from_ymd_opt(2018, 5, 5).unwrap(),
-            &full_day_duration,
+            &schedule::Schedule::from_duration_of_day(full_day_duration).into(),
         );
         let expected = Err(Error::DuplicateDate {
-            file: "".to_string(),
+            file: "holidays".to_string(),
             line_nr: 5,
         });
         assert_eq!(result, expected);
@@ -621,6 +691,7 @@ bar
                     name: "ho".to_string(),
                 },
                 given_as_range: false,
+                file: "holidays".to_string(),
                 line_nr: 1,
             },
             DayTypeEntry {
@@ -629,6 +700,7 @@ bar
                     description: "A".to_string(),
                 },
                 given_as_range: true,
+                file: "holidays".to_string(),
                 line_nr: 5,
             },
         ];
@@ -637,7 +709,7 @@ bar
             &special_required_times,
             &Date::from_ymd_opt(2018, 5, 3).unwrap(),
             &Date::from_ymd_opt(2018, 5, 5).unwrap(),
-            &full_day_duration,
+            &schedule::Schedule::from_duration_of_day(full_day_duration).into(),
         );
         let expected = Ok(vec![
             RequiredTime {
@@ -663,4 +735,62 @@ bar
         ]);
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_consolidate_required_time_with_schedule() {
+        // 2018-05-03 is a thursday
+        let special_required_times = vec![DayTypeEntry {
+            date: Date::from_ymd_opt(2018, 5, 4).unwrap(),
+            day_type: DayType::VacationHalfDay {
+                description: "A".to_string(),
+            },
+            given_as_range: false,
+            file: "holidays".to_string(),
+            line_nr: 1,
+        }];
+        let schedule: schedule::Schedule = "Mo-Th 8h, Fr 5h".parse().unwrap();
+        let result = consolidate_required_time(
+            &special_required_times,
+            &Date::from_ymd_opt(2018, 5, 3).unwrap(),
+            &Date::from_ymd_opt(2018, 5, 5).unwrap(),
+            &schedule.clone().into(),
+        )
+        .unwrap();
+        let required_times: Vec<_> = result
+            .iter()
+            .map(|required_time| required_time.required_time.num_minutes())
+            .collect();
+        assert_eq!(required_times, vec![480, 150, 0]);
+
+        // like on weekends single vacation days are rejected on free days
+        let schedule: schedule::Schedule = "Mo-Th 8h".parse().unwrap();
+        let result = consolidate_required_time(
+            &special_required_times,
+            &Date::from_ymd_opt(2018, 5, 4).unwrap(),
+            &Date::from_ymd_opt(2018, 5, 4).unwrap(),
+            &schedule.clone().into(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_consolidate_required_time_with_schedule_change() {
+        // from wednesday 2018-05-09 on only 6h per day are required
+        let schedules = schedule::ScheduleSeries::new(
+            schedule::Schedule::from_duration_of_day(chrono::Duration::hours(8)),
+            vec!["2018-05-09: Mo-Fr 6h".parse().unwrap()],
+        );
+        let result = consolidate_required_time(
+            &Vec::new(),
+            &Date::from_ymd_opt(2018, 5, 7).unwrap(),
+            &Date::from_ymd_opt(2018, 5, 10).unwrap(),
+            &schedules,
+        )
+        .unwrap();
+        let required_times: Vec<_> = result
+            .iter()
+            .map(|required_time| required_time.required_time.num_hours())
+            .collect();
+        assert_eq!(required_times, vec![8, 8, 6, 6]);
+    }
 }
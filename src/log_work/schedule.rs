@@ -0,0 +1,254 @@
+use self::chrono::Datelike;
+use super::*;
+
+/// The required working time per weekday (e.g. 'Mo-Th 8h, Fr 5h, Sa-Su 0')
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Schedule {
+    /// Indexed by the number of days from monday
+    durations: [chrono::Duration; 7],
+}
+
+impl Schedule {
+    /// The classic schedule with the same duration for monday to friday and a free weekend
+    pub fn from_duration_of_day(duration_of_day: chrono::Duration) -> Schedule {
+        let mut durations = [chrono::Duration::zero(); 7];
+        for duration in durations.iter_mut().take(5) {
+            *duration = duration_of_day;
+        }
+        Schedule { durations }
+    }
+
+    /// The required time of the weekday of the date
+    pub fn required_time(&self, date: &Date) -> chrono::Duration {
+        self.durations[date.weekday().num_days_from_monday() as usize]
+    }
+
+    pub fn is_work_day(&self, date: &Date) -> bool {
+        !self.required_time(date).is_zero()
+    }
+
+    /// The longest required time of any weekday. It serves as unit for durations spanning
+    /// several days.
+    pub fn longest_day(&self) -> chrono::Duration {
+        self.durations
+            .iter()
+            .max()
+            .copied()
+            .filter(|duration| !duration.is_zero())
+            .unwrap_or_else(|| chrono::Duration::hours(8))
+    }
+
+    /// The length of a day at the date when printing durations in days, i.e. the required time
+    /// of the weekday or for free days the longest required time of any weekday
+    pub fn duration_of_day(&self, date: &Date) -> chrono::Duration {
+        if self.is_work_day(date) {
+            self.required_time(date)
+        } else {
+            self.longest_day()
+        }
+    }
+}
+
+fn parse_weekday(s: &str) -> Result<usize> {
+    // English as well as German abbreviations
+    match s {
+        "Mo" => Ok(0),
+        "Tu" | "Di" => Ok(1),
+        "We" | "Mi" => Ok(2),
+        "Th" | "Do" => Ok(3),
+        "Fr" => Ok(4),
+        "Sa" => Ok(5),
+        "Su" | "So" => Ok(6),
+        _ => Err(Error::CommandLine(format!("Unknown weekday '{}'", s))),
+    }
+}
+
+impl std::str::FromStr for Schedule {
+    type Err = Error;
+
+    /// Parses comma separated weekdays or ranges of weekdays followed by their duration. Weekdays
+    /// which are not mentioned have a duration of 0.
+    fn from_str(s: &str) -> Result<Schedule> {
+        lazy_static::lazy_static! {
+            static ref RE: regex::Regex = regex::Regex::new(r"^(\w\w)(-(\w\w))? +(.+)$")
+                .expect("Erronuous Regular Expression for schedule parsing");
+        }
+        let mut durations = [chrono::Duration::zero(); 7];
+        for part in s.split(',').map(|part| part.trim()) {
+            let c = RE.captures(part).ok_or_else(|| {
+                Error::CommandLine(format!(
+                    "Schedule entry '{}' did not have the form 'Mo-Th 8h' or 'Fr 5h 30m'",
+                    part
+                ))
+            })?;
+            let first = parse_weekday(&c[1])?;
+            let last = match c.get(3) {
+                Some(last) => parse_weekday(last.as_str())?,
+                None => first,
+            };
+            if first > last {
+                return Err(Error::CommandLine(format!(
+                    "Weekday range '{}' does not start with the earlier day",
+                    part
+                )));
+            }
+            let duration = match &c[4] {
+                "0" => chrono::Duration::zero(),
+                duration => util::parse_duration(duration)?,
+            };
+            for d in &mut durations[first..=last] {
+                *d = duration;
+            }
+        }
+        Ok(Schedule { durations })
+    }
+}
+
+/// A schedule valid from the given date on, e.g. '2023-07-01: Mo-Th 8h'. Without a date it is
+/// valid from the beginning.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DatedSchedule {
+    pub valid_from: Option<Date>,
+    pub schedule: Schedule,
+}
+
+impl std::str::FromStr for DatedSchedule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<DatedSchedule> {
+        lazy_static::lazy_static! {
+            static ref RE: regex::Regex = regex::Regex::new(r"^(\d{4})-(\d{2})-(\d{2}): *(.*)$")
+                .expect("Erronuous Regular Expression for dated schedule parsing");
+        }
+        match RE.captures(s.trim()) {
+            Some(c) => Ok(DatedSchedule {
+                valid_from: Some(util::to_date(&c[1], &c[2], &c[3])?),
+                schedule: c[4].parse()?,
+            }),
+            None => Ok(DatedSchedule {
+                valid_from: None,
+                schedule: s.parse()?,
+            }),
+        }
+    }
+}
+
+/// The schedules over time, e.g. after a change of the contract
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScheduleSeries {
+    initial: Schedule,
+    /// Sorted by the date from which on the schedules are valid
+    changes: Vec<(Date, Schedule)>,
+}
+
+impl ScheduleSeries {
+    /// The dated schedules replace the initial one from their dates on. A schedule without a
+    /// date replaces the initial one.
+    pub fn new(initial: Schedule, schedules: Vec<DatedSchedule>) -> ScheduleSeries {
+        let mut ret = ScheduleSeries {
+            initial,
+            changes: Vec::new(),
+        };
+        for dated_schedule in schedules {
+            match dated_schedule.valid_from {
+                Some(date) => ret.changes.push((date, dated_schedule.schedule)),
+                None => ret.initial = dated_schedule.schedule,
+            }
+        }
+        ret.changes.sort_by_key(|(date, _)| *date);
+        ret
+    }
+
+    /// The schedule valid at the date
+    pub fn at(&self, date: &Date) -> &Schedule {
+        self.changes
+            .iter()
+            .rev()
+            .find(|(valid_from, _)| valid_from <= date)
+            .map_or(&self.initial, |(_, schedule)| schedule)
+    }
+
+    /// The longest required time of any weekday of any of the schedules
+    pub fn longest_day(&self) -> chrono::Duration {
+        self.changes
+            .iter()
+            .map(|(_, schedule)| schedule.longest_day())
+            .fold(self.initial.longest_day(), std::cmp::max)
+    }
+}
+
+impl From<Schedule> for ScheduleSeries {
+    fn from(schedule: Schedule) -> ScheduleSeries {
+        ScheduleSeries::new(schedule, Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_schedule() {
+        let schedule: Schedule = "Mo-Th 8h, Fr 5h 30m, Sa-Su 0".parse().unwrap();
+        let monday = Date::from_ymd_opt(2018, 5, 7).unwrap();
+        let durations: Vec<_> = (0..7)
+            .map(|d| {
+                schedule
+                    .required_time(&(monday + chrono::Duration::days(d)))
+                    .num_minutes()
+            })
+            .collect();
+        assert_eq!(durations, vec![480, 480, 480, 480, 330, 0, 0]);
+        assert_eq!(schedule.longest_day(), chrono::Duration::hours(8));
+        assert_eq!(
+            schedule.duration_of_day(&(monday + chrono::Duration::days(4))),
+            chrono::Duration::minutes(330)
+        );
+        assert_eq!(
+            schedule.duration_of_day(&(monday + chrono::Duration::days(5))),
+            chrono::Duration::hours(8)
+        );
+    }
+
+    #[test]
+    fn test_parse_schedule_defaults_to_free_days() {
+        let schedule: Schedule = "Di-Do 6h".parse().unwrap();
+        let monday = Date::from_ymd_opt(2018, 5, 7).unwrap();
+        assert!(!schedule.is_work_day(&monday));
+        assert!(schedule.is_work_day(&(monday + chrono::Duration::days(1))));
+    }
+
+    #[test]
+    fn test_parse_schedule_errors() {
+        assert!("Mo-Th".parse::<Schedule>().is_err());
+        assert!("Fr-Mo 8h".parse::<Schedule>().is_err());
+        assert!("Xx 8h".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn test_schedule_series() {
+        let schedules: Vec<DatedSchedule> = ["2018-07-01: Mo-Th 8h", "Mo-Fr 8h"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let series = ScheduleSeries::new(
+            Schedule::from_duration_of_day(chrono::Duration::hours(6)),
+            schedules,
+        );
+        // fridays before and after the change
+        let before = Date::from_ymd_opt(2018, 6, 29).unwrap();
+        let after = Date::from_ymd_opt(2018, 7, 6).unwrap();
+        assert_eq!(
+            series.at(&before).required_time(&before),
+            chrono::Duration::hours(8)
+        );
+        assert_eq!(
+            series.at(&after).required_time(&after),
+            chrono::Duration::hours(0)
+        );
+        assert_eq!(
+            series.at(&Date::from_ymd_opt(2018, 7, 1).unwrap()),
+            &"Mo-Th 8h".parse::<Schedule>().unwrap()
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use super::work_day;
+use super::*;
+
+/// The last entry of a day in progress, which runs until now
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OpenEntry {
+    pub key: String,
+    pub sub_keys: Vec<String>,
+    pub start: DateTime,
+    pub running_for: chrono::Duration,
+    pub is_work: bool,
+}
+
+/// The state of today's work
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Status {
+    pub now: DateTime,
+    pub open_entry: Option<OpenEntry>,
+    /// The time logged per key including the open entry
+    pub summary: work_day::Summary,
+    pub worked: chrono::Duration,
+    pub required: chrono::Duration,
+    pub duration_of_day: chrono::Duration,
+}
+
+impl Status {
+    /// The required time which is not worked yet
+    pub fn left(&self) -> chrono::Duration {
+        std::cmp::max(self.required - self.worked, chrono::Duration::zero())
+    }
+
+    fn format_duration(&self, duration: chrono::Duration) -> util::WorkDuration {
+        util::WorkDuration {
+            duration,
+            duration_of_day: self.duration_of_day,
+        }
+    }
+
+    /// A single line for shell prompts and status bars, e.g. 'PROJ-1 review 0:25 | left 1:05'
+    pub fn one_line(&self) -> String {
+        let left = format!("left {}", format_hours(self.left()));
+        match self.open_entry {
+            Some(ref entry) => {
+                let mut ret = entry.key.clone();
+                for sub_key in &entry.sub_keys {
+                    ret.push(' ');
+                    ret.push_str(sub_key);
+                }
+                format!("{} {} | {}", ret, format_hours(entry.running_for), left)
+            }
+            None => format!("- | {}", left),
+        }
+    }
+}
+
+/// Formats the duration as 'h:mm', negative durations (e.g. of an entry starting in the future)
+/// as '0:00'
+fn format_hours(duration: chrono::Duration) -> String {
+    let minutes = std::cmp::max(duration.num_minutes(), 0);
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "= Status at {}:", self.now.format("%F (%a) %H:%M"))?;
+        match self.open_entry {
+            Some(ref entry) => {
+                let label = if entry.is_work {
+                    "Open entry"
+                } else {
+                    "On break"
+                };
+                writeln!(f, "{:20}: {}", label, entry.key)?;
+                if !entry.sub_keys.is_empty() {
+                    writeln!(f, "{:20}: {}", "Sub keys", entry.sub_keys.join(" "))?;
+                }
+                writeln!(
+                    f,
+                    "{:20}: {:>19}",
+                    "Started at",
+                    entry.start.format("%H:%M").to_string()
+                )?;
+                writeln!(
+                    f,
+                    "{:20}: {:>19}",
+                    "Running for",
+                    self.format_duration(entry.running_for)
+                )?;
+            }
+            None => writeln!(f, "No entries yet")?,
+        }
+        for (key, duration) in &self.summary {
+            writeln!(f, "{:20}: {:>19}", key, self.format_duration(*duration))?;
+        }
+        writeln!(
+            f,
+            "{:20}: {:>19}",
+            " == Required ==",
+            self.format_duration(self.required)
+        )?;
+        writeln!(
+            f,
+            "{:20}: {:>19}",
+            " == Total ==",
+            self.format_duration(self.worked)
+        )?;
+        write!(
+            f,
+            "{:20}: {:>19}",
+            " == Left ==",
+            self.format_duration(self.left())
+        )
+    }
+}
+
+/// The status of the day in progress, whose last entry runs until `now`
+pub fn status(day: &work_day::Day, key_classes: &work_day::KeyClasses, now: &DateTime) -> Status {
+    let day = day.with_open_entry_until(now);
+    let open_entry = day.work_day.entries.last().map(|entry| OpenEntry {
+        key: entry.key.clone(),
+        sub_keys: entry.sub_keys.clone(),
+        start: day.work_day.date.and_time(entry.start_ts),
+        running_for: entry.duration,
+        is_work: key_classes.is_work(&entry.key),
+    });
+    Status {
+        now: *now,
+        open_entry,
+        summary: day.work_day.compute_summary(),
+        worked: day.worked_time(key_classes),
+        required: day.required_time.required_time,
+        duration_of_day: day.duration_of_day,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_day(txt: &str) -> work_day::Day {
+        let date = Date::from_ymd_opt(2018, 5, 9).unwrap();
+        let key_classes = work_day::KeyClasses::new(vec!["Pause".to_string()], Vec::new());
+        work_day::Day {
+            duration_of_day: chrono::Duration::hours(8),
+            required_time: required_time::RequiredTime {
+                date,
+                day_type: required_time::DayType::WorkDay,
+                required_time: chrono::Duration::hours(8),
+                line_nr: 0,
+            },
+            work_day: work_day::WorkDay::parse(
+                &mut std::io::BufReader::new(txt.as_bytes()),
+                Some(date),
+                false,
+                true,
+                &key_classes,
+                "tst_file",
+            )
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_status() {
+        let day = make_day(
+            r"-- 2018-05-09 Mi 08:00 -- Foo
+-- 2018-05-09 Mi 12:00 -- Pause
+-- 2018-05-09 Mi 12:30 -- Bar review: PROJ-1
+
+Some text",
+        );
+        let now = Date::from_ymd_opt(2018, 5, 9)
+            .unwrap()
+            .and_hms_opt(14, 5, 0)
+            .unwrap();
+        let status = status(&day, &work_day::KeyClasses::default(), &now);
+        assert_eq!(
+            status.open_entry,
+            Some(OpenEntry {
+                key: "Bar".to_string(),
+                sub_keys: vec!["review".to_string(), "PROJ-1".to_string()],
+                start: Date::from_ymd_opt(2018, 5, 9)
+                    .unwrap()
+                    .and_hms_opt(12, 30, 0)
+                    .unwrap(),
+                running_for: chrono::Duration::minutes(95),
+                is_work: true,
+            })
+        );
+        assert_eq!(status.summary["Bar"], chrono::Duration::minutes(95));
+        assert_eq!(status.summary["Pause"], chrono::Duration::minutes(30));
+        assert_eq!(status.worked, chrono::Duration::minutes(335));
+        assert_eq!(status.left(), chrono::Duration::minutes(145));
+        assert_eq!(status.one_line(), "Bar review PROJ-1 1:35 | left 2:25");
+    }
+
+    #[test]
+    fn test_status_without_entries() {
+        let mut day = make_day("-- 2018-05-09 Mi 08:00 -- Foo");
+        day.work_day.entries.clear();
+        let now = Date::from_ymd_opt(2018, 5, 9)
+            .unwrap()
+            .and_hms_opt(7, 0, 0)
+            .unwrap();
+        let status = status(&day, &work_day::KeyClasses::default(), &now);
+        assert_eq!(status.open_entry, None);
+        assert_eq!(status.one_line(), "- | left 8:00");
+    }
+
+    #[test]
+    fn test_format_hours() {
+        assert_eq!(format_hours(chrono::Duration::minutes(65)), "1:05");
+        assert_eq!(format_hours(chrono::Duration::zero()), "0:00");
+        assert_eq!(format_hours(chrono::Duration::minutes(-5)), "0:00");
+    }
+}
@@ -6,24 +6,71 @@ pub fn to_date(year: &str, month: &str, day: &str) -> Result<Date> {
     let year = year.parse::<i32>()?;
     let month = month.parse::<u32>()?;
     let day = day.parse::<u32>()?;
-    Date::from_ymd_opt(year, month, day).ok_or_else(|| Error::ParseDay)
+    Date::from_ymd_opt(year, month, day).ok_or(Error::ParseDay)
 }
 
-pub struct HourMinuteDuration<'a> {
-    pub duration: &'a chrono::Duration,
+/// Parses a duration of the form '[-]<hours>h <minutes>m' (e.g. '8h', '-1h 30m' or '45m')
+pub fn parse_duration(s: &str) -> Result<chrono::Duration> {
+    let re = regex::Regex::new(r"^(-)?((\d+)h)? ?((\d+)m)?$").expect("broken regular expression");
+    match re.captures(s) {
+        Some(c) => {
+            let h = c.get(3).map_or("0", |m| m.as_str());
+            let h = h.parse::<i64>()?;
+            let m = c.get(5).map_or("0", |m| m.as_str());
+            let m = m.parse::<i64>()?;
+            let duration = chrono::Duration::hours(h) + chrono::Duration::minutes(m);
+            if c.get(1).is_some() {
+                Ok(-duration)
+            } else {
+                Ok(duration)
+            }
+        }
+        None => Err(Error::CommandLine(
+            "Command line argument did not have the form '[-]<hours>h <minutes>m'".to_string(),
+        )),
+    }
 }
 
-impl<'a> std::fmt::Display for HourMinuteDuration<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "{:>2}:{:02}",
-            self.duration.num_hours(),
-            self.duration.num_minutes() % 60
-        )
+/// Serializes a duration as its number of minutes
+pub fn serialize_minutes<S>(
+    duration: &chrono::Duration,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_i64(duration.num_minutes())
+}
+
+/// Serializes an optional duration as its number of minutes or `null`
+pub fn serialize_opt_minutes<S>(
+    duration: &Option<chrono::Duration>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match duration {
+        Some(duration) => serializer.serialize_some(&duration.num_minutes()),
+        None => serializer.serialize_none(),
     }
 }
 
+/// Serializes the durations of a summary as their number of minutes
+pub fn serialize_summary_minutes<S>(
+    summary: &std::collections::BTreeMap<String, chrono::Duration>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.collect_map(
+        summary
+            .iter()
+            .map(|(key, duration)| (key, duration.num_minutes())),
+    )
+}
+
 pub struct WorkDuration {
     pub duration: chrono::Duration,
     pub duration_of_day: chrono::Duration,
@@ -31,7 +78,13 @@ pub struct WorkDuration {
 
 impl std::fmt::Display for WorkDuration {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let mut remaining_minutes = self.duration.num_minutes();
+        // a deficit is printed as its absolute value prefixed with a '-'
+        let sign = if self.duration < chrono::Duration::zero() {
+            "-"
+        } else {
+            ""
+        };
+        let mut remaining_minutes = self.duration.num_minutes().abs();
         let days = remaining_minutes / self.duration_of_day.num_minutes();
         remaining_minutes %= self.duration_of_day.num_minutes();
         let hours = remaining_minutes / 60;
@@ -42,15 +95,74 @@ impl std::fmt::Display for WorkDuration {
 
         let txt = if days > 0 {
             format!(
-                "{}d {}h {:2}m ({:>5.2}h)",
-                days, hours, minutes, total_minutes
+                "{}{}d {}h {:2}m ({:>5.2}h)",
+                sign, days, hours, minutes, total_minutes
             )
         } else if hours > 0 {
-            format!("{}h {:2}m ({:>5.2}h)", hours, minutes, total_minutes)
+            format!(
+                "{}{}h {:2}m ({:>5.2}h)",
+                sign, hours, minutes, total_minutes
+            )
         } else {
-            format!("{:2}m ({:>5.2}h)", minutes, total_minutes)
+            // the sign has to be padded together with the minutes to stay next to them
+            format!(
+                "{:>2}m ({:>5.2}h)",
+                format!("{}{}", sign, minutes),
+                total_minutes
+            )
         };
 
         f.pad(txt.as_str())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format_work_duration(duration: chrono::Duration) -> String {
+        format!(
+            "{}",
+            WorkDuration {
+                duration,
+                duration_of_day: chrono::Duration::hours(8)
+            }
+        )
+    }
+
+    #[test]
+    fn test_work_duration_positive() {
+        assert_eq!(
+            format_work_duration(chrono::Duration::minutes(8 * 60 + 90)),
+            "1d 1h 30m ( 9.50h)"
+        );
+        assert_eq!(
+            format_work_duration(chrono::Duration::minutes(5)),
+            " 5m ( 0.08h)"
+        );
+    }
+
+    #[test]
+    fn test_work_duration_negative() {
+        assert_eq!(
+            format_work_duration(-chrono::Duration::minutes(8 * 60 + 90)),
+            "-1d 1h 30m (-9.50h)"
+        );
+        assert_eq!(
+            format_work_duration(-chrono::Duration::minutes(75)),
+            "-1h 15m (-1.25h)"
+        );
+        assert_eq!(
+            format_work_duration(-chrono::Duration::minutes(5)),
+            "-5m (-0.08h)"
+        );
+        assert_eq!(
+            format_work_duration(-chrono::Duration::minutes(15)),
+            "-15m (-0.25h)"
+        );
+        assert_eq!(
+            format_work_duration(-chrono::Duration::minutes(65)),
+            "-1h  5m (-1.08h)"
+        );
+    }
+}
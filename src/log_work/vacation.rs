@@ -0,0 +1,368 @@
+use self::chrono::Datelike;
+use super::*;
+
+/// The annual vacation entitlement in days valid from the given year on, e.g. '2024: 28'. Without a
+/// year it is valid from the beginning.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DatedEntitlement {
+    pub valid_from: Option<i32>,
+    pub days: f64,
+}
+
+fn parse_days(s: &str) -> Result<f64> {
+    match s.trim().parse::<f64>() {
+        Ok(days) if days >= 0.0 => Ok(days),
+        _ => Err(Error::CommandLine(format!(
+            "Expected a non-negative number of days, got '{}'",
+            s
+        ))),
+    }
+}
+
+impl std::str::FromStr for DatedEntitlement {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<DatedEntitlement> {
+        lazy_static::lazy_static! {
+            static ref RE: regex::Regex = regex::Regex::new(r"^(\d{4}): *(.*)$")
+                .expect("Erronuous Regular Expression for entitlement parsing");
+        }
+        match RE.captures(s.trim()) {
+            Some(c) => Ok(DatedEntitlement {
+                valid_from: Some(c[1].parse()?),
+                days: parse_days(&c[2])?,
+            }),
+            None => Ok(DatedEntitlement {
+                valid_from: None,
+                days: parse_days(s)?,
+            }),
+        }
+    }
+}
+
+/// The day of the year on which the carry-over of the previous year expires, e.g. '03-31'
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Expiry {
+    month: u32,
+    day: u32,
+}
+
+impl Expiry {
+    fn in_year(&self, year: i32) -> Result<Date> {
+        Date::from_ymd_opt(year, self.month, self.day).ok_or(Error::ParseDay)
+    }
+}
+
+impl std::str::FromStr for Expiry {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Expiry> {
+        lazy_static::lazy_static! {
+            static ref RE: regex::Regex = regex::Regex::new(r"^(\d{2})-(\d{2})$")
+                .expect("Erronuous Regular Expression for expiry parsing");
+        }
+        let error = || Error::CommandLine(format!("Expiry '{}' did not have the form 'mm-dd'", s));
+        let c = RE.captures(s.trim()).ok_or_else(error)?;
+        let expiry = Expiry {
+            month: c[1].parse()?,
+            day: c[2].parse()?,
+        };
+        // the 29th of february would not exist in most years
+        if expiry.in_year(2001).is_err() {
+            return Err(error());
+        }
+        Ok(expiry)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VacationConfig {
+    pub entitlements: Vec<DatedEntitlement>,
+    /// The days carried over into the first year
+    pub opening_carry_over: f64,
+    /// Without an expiry the carry-over never expires
+    pub carry_over_expiry: Option<Expiry>,
+}
+
+impl VacationConfig {
+    /// The entitlement of the year, i.e. the one with the latest start not after the year
+    fn entitlement(&self, year: i32) -> f64 {
+        self.entitlements
+            .iter()
+            .filter(|entitlement| entitlement.valid_from.is_none_or(|from| from <= year))
+            .max_by_key(|entitlement| entitlement.valid_from)
+            .map_or(0.0, |entitlement| entitlement.days)
+    }
+
+    /// The years of the vacation account. It starts with the first year with vacation or with a
+    /// dated entitlement, but not before the first dated entitlement if there is no undated one.
+    /// It lasts at least until the year of today.
+    pub fn years(
+        &self,
+        entries: &[required_time::DayTypeEntry],
+        today: &Date,
+    ) -> std::ops::RangeInclusive<i32> {
+        let vacation_years: Vec<i32> = entries
+            .iter()
+            .filter(|entry| vacation_days(entry.day_type()) > 0.0)
+            .map(|entry| entry.date().year())
+            .collect();
+        let first_dated_year = self
+            .entitlements
+            .iter()
+            .filter_map(|entitlement| entitlement.valid_from)
+            .min();
+        let has_undated = self
+            .entitlements
+            .iter()
+            .any(|entitlement| entitlement.valid_from.is_none());
+        let first_vacation_year = vacation_years.iter().min().copied();
+        let first_year = match (first_dated_year, first_vacation_year) {
+            (Some(dated), Some(vacation)) if has_undated => std::cmp::min(dated, vacation),
+            (Some(dated), _) => dated,
+            (None, Some(vacation)) => vacation,
+            (None, None) => today.year(),
+        };
+        let last_year = vacation_years
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or(today.year())
+            .max(today.year());
+        std::cmp::min(first_year, last_year)..=last_year
+    }
+}
+
+/// The number of vacation days a day of the type takes from the entitlement
+fn vacation_days(day_type: &required_time::DayType) -> f64 {
+    match *day_type {
+        required_time::DayType::Vacation { description: _ } => 1.0,
+        required_time::DayType::VacationHalfDay { description: _ } => 0.5,
+        _ => 0.0,
+    }
+}
+
+/// The vacation account of one year. All values are in days.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VacationYear {
+    pub year: i32,
+    pub entitlement: f64,
+    pub carry_over: f64,
+    pub carry_over_expiry: Option<Date>,
+    /// The carry-over which expired without being taken
+    pub expired: f64,
+    /// The carry-over which will expire unless more vacation is planned before the expiry
+    pub expiring: f64,
+    /// The vacation until today
+    pub taken: f64,
+    /// The vacation after today
+    pub planned: f64,
+}
+
+impl VacationYear {
+    pub fn remaining(&self) -> f64 {
+        self.entitlement + self.carry_over - self.expired - self.taken - self.planned
+    }
+}
+
+impl std::fmt::Display for VacationYear {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "= Vacation {}:", self.year)?;
+        writeln!(f, "{:20}: {:>8}", "Entitlement", self.entitlement)?;
+        writeln!(f, "{:20}: {:>8}", "Carry-over", self.carry_over)?;
+        if let Some(expiry) = self.carry_over_expiry {
+            if self.expired != 0.0 {
+                writeln!(
+                    f,
+                    "{:20}: {:>8}",
+                    format!("Expired {}", expiry.format("%F")),
+                    self.expired
+                )?;
+            }
+            if self.expiring != 0.0 {
+                writeln!(
+                    f,
+                    "{:20}: {:>8}",
+                    format!("Expiring {}", expiry.format("%F")),
+                    self.expiring
+                )?;
+            }
+        }
+        writeln!(f, "{:20}: {:>8}", "Taken", self.taken)?;
+        writeln!(f, "{:20}: {:>8}", "Planned", self.planned)?;
+        write!(f, "{:20}: {:>8}", " == Remaining ==", self.remaining())
+    }
+}
+
+/// Computes the vacation account of the years. Vacation only counts on the work days of the
+/// schedule which are no holidays. Vacation taken before the expiry of the carry-over is taken
+/// from the carry-over first.
+pub fn compute_vacation_account(
+    entries: &[required_time::DayTypeEntry],
+    years: std::ops::RangeInclusive<i32>,
+    schedules: &schedule::ScheduleSeries,
+    config: &VacationConfig,
+    today: &Date,
+) -> Result<Vec<VacationYear>> {
+    let mut ret = Vec::new();
+    let mut carry_over = config.opening_carry_over;
+    for year in years {
+        let required_times = required_time::consolidate_required_time(
+            &entries.to_vec(),
+            &Date::from_ymd_opt(year, 1, 1).ok_or(Error::ParseDay)?,
+            &Date::from_ymd_opt(year, 12, 31).ok_or(Error::ParseDay)?,
+            schedules,
+        )?;
+        let carry_over_expiry = match config.carry_over_expiry {
+            Some(expiry) => Some(expiry.in_year(year)?),
+            None => None,
+        };
+        let mut vacation_year = VacationYear {
+            year,
+            entitlement: config.entitlement(year),
+            carry_over,
+            carry_over_expiry,
+            expired: 0.0,
+            expiring: 0.0,
+            taken: 0.0,
+            planned: 0.0,
+        };
+        let mut days_before_expiry = 0.0;
+        for required_time in &required_times {
+            if !schedules
+                .at(&required_time.date)
+                .is_work_day(&required_time.date)
+            {
+                continue;
+            }
+            let days = vacation_days(&required_time.day_type);
+            if required_time.date <= *today {
+                vacation_year.taken += days;
+            } else {
+                vacation_year.planned += days;
+            }
+            if carry_over_expiry.is_some_and(|expiry| required_time.date <= expiry) {
+                days_before_expiry += days;
+            }
+        }
+        if let Some(expiry) = carry_over_expiry {
+            let unused = (carry_over.max(0.0) - days_before_expiry).max(0.0);
+            if expiry < *today {
+                vacation_year.expired = unused;
+            } else {
+                vacation_year.expiring = unused;
+            }
+        }
+        carry_over = vacation_year.remaining() - vacation_year.expiring;
+        ret.push(vacation_year);
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_entries(txt: &str) -> Vec<required_time::DayTypeEntry> {
+        required_time::parse_required_time(&mut std::io::BufReader::new(txt.as_bytes()), "tst_file")
+            .unwrap()
+    }
+
+    fn config(opening_carry_over: f64, expiry: Option<&str>) -> VacationConfig {
+        VacationConfig {
+            entitlements: vec!["30".parse().unwrap(), "2019: 28".parse().unwrap()],
+            opening_carry_over,
+            carry_over_expiry: expiry.map(|expiry| expiry.parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_parse_entitlement() {
+        assert_eq!(
+            "2024: 27.5".parse::<DatedEntitlement>(),
+            Ok(DatedEntitlement {
+                valid_from: Some(2024),
+                days: 27.5
+            })
+        );
+        assert_eq!(
+            "30".parse::<DatedEntitlement>(),
+            Ok(DatedEntitlement {
+                valid_from: None,
+                days: 30.0
+            })
+        );
+        assert!("-3".parse::<DatedEntitlement>().is_err());
+        assert!("2024: x".parse::<DatedEntitlement>().is_err());
+        assert!("02-30".parse::<Expiry>().is_err());
+        assert!("3-31".parse::<Expiry>().is_err());
+    }
+
+    #[test]
+    fn test_entitlement_per_year() {
+        let config = config(0.0, None);
+        assert_eq!(config.entitlement(2018), 30.0);
+        assert_eq!(config.entitlement(2019), 28.0);
+        assert_eq!(config.entitlement(2020), 28.0);
+    }
+
+    #[test]
+    fn test_vacation_account() {
+        // 2018-05-07 is a monday, the range includes a weekend and 2018-05-10 is a holiday
+        let entries = parse_entries(
+            r"2018-05-10 -- F Christi Himmelfahrt
+2018-05-07--2018-05-13 -- U Urlaub
+2018-12-24 -- H Heiligabend
+2019-02-01 -- U Brückentag",
+        );
+        let config = config(2.0, None);
+        let today = Date::from_ymd_opt(2018, 10, 1).unwrap();
+        let years = config.years(&entries, &today);
+        assert_eq!(years, 2018..=2019);
+        let account = compute_vacation_account(
+            &entries,
+            years,
+            &schedule::Schedule::from_duration_of_day(chrono::Duration::hours(8)).into(),
+            &config,
+            &today,
+        )
+        .unwrap();
+        assert_eq!(account.len(), 2);
+        assert_eq!(account[0].taken, 4.0);
+        assert_eq!(account[0].planned, 0.5);
+        assert_eq!(account[0].remaining(), 27.5);
+        assert_eq!(account[1].carry_over, 27.5);
+        assert_eq!(account[1].planned, 1.0);
+        assert_eq!(account[1].remaining(), 54.5);
+    }
+
+    #[test]
+    fn test_vacation_account_with_expiry() {
+        let entries = parse_entries(
+            r"2018-03-01--2018-03-02 -- U Ski
+2018-06-01 -- U Sommer",
+        );
+        let config = config(5.0, Some("03-31"));
+        let schedules: schedule::ScheduleSeries =
+            schedule::Schedule::from_duration_of_day(chrono::Duration::hours(8)).into();
+
+        // before the expiry the unused carry-over is still available
+        let today = Date::from_ymd_opt(2018, 2, 1).unwrap();
+        let account =
+            compute_vacation_account(&entries, 2018..=2018, &schedules, &config, &today).unwrap();
+        assert_eq!(account[0].expired, 0.0);
+        assert_eq!(account[0].expiring, 3.0);
+        assert_eq!(account[0].remaining(), 32.0);
+
+        let today = Date::from_ymd_opt(2018, 7, 1).unwrap();
+        let account =
+            compute_vacation_account(&entries, 2018..=2019, &schedules, &config, &today).unwrap();
+        assert_eq!(account[0].expired, 3.0);
+        assert_eq!(account[0].taken, 3.0);
+        assert_eq!(account[0].remaining(), 29.0);
+        // without vacation the whole carry-over expires
+        assert_eq!(account[1].carry_over, 29.0);
+        assert_eq!(account[1].expiring, 29.0);
+        assert_eq!(account[1].remaining(), 57.0);
+    }
+}
@@ -10,16 +10,111 @@ struct EntryRaw {
     pub raw_data: String,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize)]
 pub struct Entry {
     pub start_ts: Time,
+    #[serde(serialize_with = "util::serialize_minutes")]
     pub duration: chrono::Duration,
     pub key: String,
     pub sub_keys: Vec<String>,
+    /// The key as given in the .work-file if it was replaced through an alias
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(skip)]
     pub raw_data: String,
 }
 
+/// Splits a key naming several tickets joined by '|' into the tickets and their weights (see
+/// `Entry::split`). A key without '|' is returned as it is with the weight 1.
+pub fn split_key(key: &str) -> Vec<(&str, i64)> {
+    if !key.contains('|') {
+        return vec![(key, 1)];
+    }
+    key.split('|')
+        .filter(|key| !key.is_empty())
+        .map(|key| match key.rsplit_once('*') {
+            Some((name, weight)) => match weight.parse::<i64>() {
+                Ok(weight) if weight > 0 && !name.is_empty() => (name, weight),
+                _ => (key, 1),
+            },
+            None => (key, 1),
+        })
+        .collect()
+}
+
 impl Entry {
+    /// Splits an entry whose key names several tickets joined by '|' (e.g. 'PROJ-1*2|PROJ-2')
+    /// into one entry per ticket. The duration is distributed according to the optional integer
+    /// weights after the '*' (default 1) and the resulting entries follow each other. Entries
+    /// with a single key are returned unchanged.
+    pub fn split(&self) -> Vec<Entry> {
+        if !self.key.contains('|') {
+            return vec![self.clone()];
+        }
+        let keys = split_key(&self.key);
+        if keys.is_empty() {
+            return vec![self.clone()];
+        }
+        // an alias replaces the tickets one by one, so the original tickets line up with the keys
+        // unless a replacement names several tickets. Then the new tickets are attributed to the
+        // replaced ones.
+        let aliases: Vec<Option<String>> = match self.alias {
+            Some(ref alias) if split_key(alias).len() == keys.len() => split_key(alias)
+                .iter()
+                .zip(&keys)
+                .map(|(&(original, _), &(name, _))| {
+                    Some(original.to_string()).filter(|o| o != name)
+                })
+                .collect(),
+            Some(ref alias) => {
+                let originals = split_key(alias);
+                let replaced = itertools::join(
+                    originals
+                        .iter()
+                        .map(|&(original, _)| original)
+                        .filter(|original| keys.iter().all(|&(name, _)| name != *original)),
+                    "|",
+                );
+                keys.iter()
+                    .map(|&(name, _)| {
+                        Some(replaced.clone())
+                            .filter(|_| originals.iter().all(|&(original, _)| original != name))
+                    })
+                    .collect()
+            }
+            None => vec![None; keys.len()],
+        };
+        let total_weight: i64 = keys.iter().map(|(_, weight)| weight).sum();
+        let total_minutes = self.duration.num_minutes();
+        let mut minutes: Vec<i64> = keys
+            .iter()
+            .map(|(_, weight)| total_minutes * weight / total_weight)
+            .collect();
+        // hand out the minutes lost by rounding down to the first tickets
+        let remainder = total_minutes - minutes.iter().sum::<i64>();
+        for m in minutes.iter_mut().take(remainder as usize) {
+            *m += 1;
+        }
+        let mut start_ts = self.start_ts;
+        keys.iter()
+            .zip(minutes)
+            .zip(aliases)
+            .map(|(((name, _), minutes), alias)| {
+                let duration = chrono::Duration::minutes(minutes);
+                let entry = Entry {
+                    start_ts,
+                    duration,
+                    key: name.to_string(),
+                    sub_keys: self.sub_keys.clone(),
+                    alias,
+                    raw_data: self.raw_data.clone(),
+                };
+                start_ts += duration;
+                entry
+            })
+            .collect()
+    }
+
     fn from(entries: Vec<EntryRaw>) -> Vec<Entry> {
         let mut ret = Vec::new();
         if entries.is_empty() {
@@ -36,6 +131,7 @@ impl Entry {
                         duration,
                         key: old_entry.key,
                         sub_keys: old_entry.sub_keys,
+                        alias: None,
                         raw_data: old_entry.raw_data,
                     });
                     Some(new_entry)
@@ -49,19 +145,66 @@ impl Entry {
             duration: chrono::Duration::minutes(0),
             key: old_entry.key,
             sub_keys: old_entry.sub_keys,
+            alias: None,
             raw_data: old_entry.raw_data,
         });
         ret
     }
 }
 
+/// How the time logged for a key is counted
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeyClass {
+    Work,
+    /// A pause. Each day has to end on one.
+    Break,
+    /// Logged time which does not count as work (e.g. 'Private' or 'Doctor')
+    Excluded,
+}
+
+/// The keys which don't count as work. All other keys do.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyClasses {
+    break_keys: std::collections::BTreeSet<String>,
+    excluded_keys: std::collections::BTreeSet<String>,
+}
+
+impl Default for KeyClasses {
+    fn default() -> KeyClasses {
+        KeyClasses::new(vec!["Pause".to_string()], Vec::new())
+    }
+}
+
+impl KeyClasses {
+    pub fn new(break_keys: Vec<String>, excluded_keys: Vec<String>) -> KeyClasses {
+        KeyClasses {
+            break_keys: break_keys.into_iter().collect(),
+            excluded_keys: excluded_keys.into_iter().collect(),
+        }
+    }
+
+    pub fn classify(&self, key: &str) -> KeyClass {
+        if self.break_keys.contains(key) {
+            KeyClass::Break
+        } else if self.excluded_keys.contains(key) {
+            KeyClass::Excluded
+        } else {
+            KeyClass::Work
+        }
+    }
+
+    pub fn is_work(&self, key: &str) -> bool {
+        self.classify(key) == KeyClass::Work
+    }
+}
+
 #[derive(Debug)]
 enum EntriesLine<'a> {
     Captures(regex::Captures<'a>),
     Line,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize)]
 pub struct WorkDay {
     pub date: Date,
     pub entries: Vec<Entry>,
@@ -72,10 +215,13 @@ impl WorkDay {
     fn read_line(stream: &mut dyn std::io::BufRead) -> Result<(bool, String)> {
         let mut line = String::new();
         let num_bytes = stream.read_line(&mut line)?;
-        Ok(((num_bytes != 0 && line != "\n" && line.chars().nth(0).unwrap() != '#'), line))
+        Ok((
+            (num_bytes != 0 && line != "\n" && line.chars().nth(0).unwrap() != '#'),
+            line,
+        ))
     }
 
-    fn parse_entries_line(line: &str) -> EntriesLine {
+    fn parse_entries_line(line: &str) -> EntriesLine<'_> {
         lazy_static::lazy_static! {
             static ref RE: regex::Regex = regex::Regex::new(
                 "^-- (\\d{4})-(\\d{2})-(\\d{2}) ([^ ]+ )?(\\d{2}):(\\d{2}) -- (.*)\n?$"
@@ -94,9 +240,7 @@ impl WorkDay {
         if description.is_empty() {
             return (String::new(), Vec::new());
         }
-        let mut iter = description
-            .split(|c| c == ' ' || c == ':')
-            .filter(|x| !x.is_empty());
+        let mut iter = description.split([' ', ':']).filter(|x| !x.is_empty());
         (
             iter.next().unwrap_or("").to_string(),
             iter.map(|x| x.to_owned()).collect(),
@@ -116,9 +260,7 @@ impl WorkDay {
         let minute = minute.parse::<u32>()?;
         let (key, sub_keys) = WorkDay::parse_description(desc);
         let date = util::to_date(year, month, day)?;
-        let start_ts = date
-            .and_hms_opt(hour, minute, 0)
-            .ok_or_else(|| Error::ParseTime)?;
+        let start_ts = date.and_hms_opt(hour, minute, 0).ok_or(Error::ParseTime)?;
 
         Ok(EntryRaw {
             start_ts,
@@ -128,15 +270,20 @@ impl WorkDay {
         })
     }
 
+    /// Parses a .work-file. The file of a day still `in_progress` may end on an open entry, i.e.
+    /// its last entry does not have to be a break.
     pub fn parse(
         stream: &mut dyn std::io::BufRead,
         expected_date: Option<Date>,
         be_lenient: bool,
+        in_progress: bool,
+        key_classes: &KeyClasses,
         file: &str,
     ) -> Result<WorkDay> {
         let mut line_nr = 0u32;
         let (mut non_empty, mut line) = WorkDay::read_line(stream)?;
-        while !non_empty {
+        // skip the leading empty lines and comments, an empty line means the end of the file
+        while !non_empty && !line.is_empty() {
             line_nr += 1;
             let (tmp_non_empty, tmp_line) = WorkDay::read_line(stream)?;
             non_empty = tmp_non_empty;
@@ -181,11 +328,14 @@ impl WorkDay {
             let (_, tmp_line) = WorkDay::read_line(stream)?;
             line = tmp_line;
         }
-        if !entries.is_empty() && &entries.last().unwrap().key != "Pause" {
+        if !in_progress
+            && !entries.is_empty()
+            && key_classes.classify(&entries.last().unwrap().key) != KeyClass::Break
+        {
             if be_lenient {
                 // TODO: log a warning using a logger
-                println!(
-                    "WARNING: Missing 'Pause' as last entry for the day for file '{}'!",
+                eprintln!(
+                    "WARNING: Missing break (e.g. 'Pause') as last entry for the day for file '{}'!",
                     file
                 );
             } else {
@@ -282,7 +432,14 @@ impl WorkDay {
         Ok(entries)
     }
 
-    pub fn parse_file(file_name: &std::path::PathBuf, be_lenient: bool) -> Result<WorkDay> {
+    /// Parses a .work-file. The file named after the date `in_progress` (e.g. today) may end on an
+    /// open entry.
+    pub fn parse_file(
+        file_name: &std::path::PathBuf,
+        be_lenient: bool,
+        in_progress: Option<Date>,
+        key_classes: &KeyClasses,
+    ) -> Result<WorkDay> {
         lazy_static::lazy_static! {
             static ref RE: regex::Regex =
                 regex::Regex::new(r"(^|/)(\d{4})(\d{2})(\d{2})(_.*)\.work$")
@@ -308,12 +465,19 @@ impl WorkDay {
         };
         let file = std::fs::File::open(file_name)?;
         let mut fstream = std::io::BufReader::new(file);
-        WorkDay::parse(&mut fstream, expected_date, be_lenient, file_name_str)
+        WorkDay::parse(
+            &mut fstream,
+            expected_date,
+            be_lenient,
+            expected_date.is_some() && expected_date == in_progress,
+            key_classes,
+            file_name_str,
+        )
     }
 
     pub fn compute_summary(&self) -> Summary {
         let mut ret = Summary::new();
-        for entry in &self.entries {
+        for entry in self.entries.iter().flat_map(|entry| entry.split()) {
             ret.entry(entry.key.clone())
                 .and_modify(|e| *e += entry.duration)
                 .or_insert(entry.duration);
@@ -330,18 +494,51 @@ impl WorkDay {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, serde::Serialize)]
 pub struct Day {
+    #[serde(serialize_with = "util::serialize_minutes")]
     pub duration_of_day: chrono::Duration,
     pub required_time: required_time::RequiredTime,
     pub work_day: WorkDay,
 }
 
+impl Day {
+    /// The logged time of the day without the breaks and the excluded keys
+    pub fn worked_time(&self, key_classes: &KeyClasses) -> chrono::Duration {
+        self.work_day
+            .entries
+            .iter()
+            .flat_map(|entry| entry.split())
+            .filter(|entry| key_classes.is_work(&entry.key))
+            .fold(chrono::Duration::hours(0), |sum, entry| {
+                sum + entry.duration
+            })
+    }
+
+    /// A copy of the day whose last entry runs until `now`. As long as the day is in progress,
+    /// its last entry is open and has no duration yet.
+    pub fn with_open_entry_until(&self, now: &DateTime) -> Day {
+        let mut work_day = self.work_day.clone();
+        if let Some(entry) = work_day.entries.last_mut() {
+            let start = work_day.date.and_time(entry.start_ts);
+            entry.duration = std::cmp::max(*now - start, chrono::Duration::zero());
+        }
+        Day {
+            duration_of_day: self.duration_of_day,
+            required_time: self.required_time.clone(),
+            work_day,
+        }
+    }
+}
+
 pub type Summary = std::collections::BTreeMap<String, chrono::Duration>;
 
 pub struct DaySummary<'a> {
     pub day: &'a Day,
+    pub key_classes: &'a KeyClasses,
     pub verbose: bool,
+    /// The balance of the flextime account after this day, if the day was booked
+    pub balance: Option<chrono::Duration>,
 }
 
 impl<'a> std::fmt::Display for DaySummary<'a> {
@@ -362,18 +559,20 @@ impl<'a> std::fmt::Display for DaySummary<'a> {
             }
         }
         writeln!(f, "= {}", self.day.required_time)?;
+        let alias_sources = alias::AliasSources::from_entries(&self.day.work_day.entries);
         let mut sum = chrono::Duration::hours(0);
         for (key, duration) in self.day.work_day.compute_summary().iter() {
             writeln!(
                 f,
-                "{:20}: {:>19}",
+                "{:20}: {:>19}{}",
                 key,
                 util::WorkDuration {
                     duration_of_day,
                     duration: *duration
-                }
+                },
+                alias_sources.describe(key)
             )?;
-            if key != "Pause" {
+            if self.key_classes.is_work(key) {
                 sum += *duration;
             }
         }
@@ -395,6 +594,17 @@ impl<'a> std::fmt::Display for DaySummary<'a> {
                 duration: sum
             }
         )?;
+        if let Some(balance) = self.balance {
+            writeln!(
+                f,
+                "{:20}: {:>19}",
+                " == Balance ==",
+                util::WorkDuration {
+                    duration_of_day,
+                    duration: balance
+                }
+            )?;
+        }
         Ok(())
     }
 }
@@ -405,15 +615,20 @@ pub struct Days {
 }
 
 impl Days {
+    /// Parses the .work-files in sorted order and returns each of them with its day. The file of
+    /// the date `in_progress` (e.g. today) may end on an open entry.
     pub fn parse_work_files(
         mut files: Vec<std::path::PathBuf>,
         be_lenient: bool,
-    ) -> Vec<Result<WorkDay>> {
+        in_progress: Option<Date>,
+        key_classes: &KeyClasses,
+    ) -> Vec<(std::path::PathBuf, Result<WorkDay>)> {
         files.sort();
-        let mut ret: Vec<Result<WorkDay>> = Vec::new();
+        let mut ret: Vec<(std::path::PathBuf, Result<WorkDay>)> = Vec::new();
         ret.reserve_exact(files.len());
-        for ref file in files {
-            ret.push(WorkDay::parse_file(file, be_lenient));
+        for file in files {
+            let work_day = WorkDay::parse_file(&file, be_lenient, in_progress, key_classes);
+            ret.push((file, work_day));
         }
         ret
     }
@@ -421,7 +636,7 @@ impl Days {
     pub fn join_work_and_requirement(
         work_days: &std::collections::BTreeMap<Date, WorkDay>,
         required_times: &Vec<required_time::RequiredTime>,
-        duration_of_day: &chrono::Duration,
+        schedules: &schedule::ScheduleSeries,
     ) -> Days {
         let mut days = Vec::new();
         days.reserve_exact(required_times.len());
@@ -436,7 +651,9 @@ impl Days {
             };
 
             days.push(Day {
-                duration_of_day: *duration_of_day,
+                duration_of_day: schedules
+                    .at(&required_time.date)
+                    .duration_of_day(&required_time.date),
                 required_time: (*required_time).clone(),
                 work_day,
             });
@@ -466,7 +683,14 @@ mod tests {
         let txt = txt.as_bytes();
         let mut txt = io::BufReader::new(txt);
         let expected_date = Date::from_ymd_opt(2018, 5, 3).unwrap();
-        let entries = WorkDay::parse(&mut txt, Some(expected_date), false, "tst_file");
+        let entries = WorkDay::parse(
+            &mut txt,
+            Some(expected_date),
+            false,
+            false,
+            &KeyClasses::default(),
+            "tst_file",
+        );
         let expected_error = Err(Error::UnexpectedDate {
             file: "tst_file".to_string(),
             line_nr: 1,
@@ -484,7 +708,14 @@ mod tests {
         let txt = txt.as_bytes();
         let mut txt = io::BufReader::new(txt);
         let expected_date = Date::from_ymd_opt(2018, 5, 3).unwrap();
-        let entries = WorkDay::parse(&mut txt, Some(expected_date), false, "tst_file");
+        let entries = WorkDay::parse(
+            &mut txt,
+            Some(expected_date),
+            false,
+            false,
+            &KeyClasses::default(),
+            "tst_file",
+        );
         let expected_error = Err(Error::UnexpectedDate {
             file: "tst_file".to_string(),
             line_nr: 3,
@@ -502,7 +733,14 @@ mod tests {
         let txt = txt.as_bytes();
         let mut txt = io::BufReader::new(txt);
         let expected_date = Date::from_ymd_opt(2018, 5, 3).unwrap();
-        let entries = WorkDay::parse(&mut txt, Some(expected_date), false, "tst_file");
+        let entries = WorkDay::parse(
+            &mut txt,
+            Some(expected_date),
+            false,
+            false,
+            &KeyClasses::default(),
+            "tst_file",
+        );
         let expected_error = Err(Error::UnexpectedDate {
             file: "tst_file".to_string(),
             line_nr: 3,
@@ -517,7 +755,14 @@ mod tests {
         let txt: &str = r"-- 2018-05-04 Mo 12:27 -- Foo Bar Baz
 -- 2018-05-04 Mo 12:26 -- Foo Bar Baz";
         let mut txt = io::BufReader::new(txt.as_bytes());
-        let entries = WorkDay::parse(&mut txt, None, false, "tst_file");
+        let entries = WorkDay::parse(
+            &mut txt,
+            None,
+            false,
+            false,
+            &KeyClasses::default(),
+            "tst_file",
+        );
         let expected_error = Err(Error::TimeNotMonotonic {
             file: "tst_file".to_string(),
             line_nr: 2,
@@ -532,7 +777,14 @@ mod tests {
 
 -- 2018-05-04 Mo 12:39 -- Baz";
         let mut txt = io::BufReader::new(txt.as_bytes());
-        let entries = WorkDay::parse(&mut txt, None, false, "tst_file");
+        let entries = WorkDay::parse(
+            &mut txt,
+            None,
+            false,
+            false,
+            &KeyClasses::default(),
+            "tst_file",
+        );
         let expected_error = Err(Error::EntryAfterSeparator {
             file: "tst_file".to_string(),
             line_nr: 4,
@@ -546,20 +798,120 @@ mod tests {
 -- 2018-05-04 Mo 12:29 -- Bar
 -- 2018-05-04 Mo 12:39 -- Baz";
         let mut txt = io::BufReader::new(txt.as_bytes());
-        let entries = WorkDay::parse(&mut txt, None, false, "tst_file");
+        let entries = WorkDay::parse(
+            &mut txt,
+            None,
+            false,
+            false,
+            &KeyClasses::default(),
+            "tst_file",
+        );
         let expected_error = Err(Error::MissingFinalPause {
             file: "tst_file".to_string(),
         });
         assert_eq!(expected_error, entries);
     }
 
+    #[test]
+    fn test_parse_empty_file() {
+        let date = Date::from_ymd_opt(2018, 5, 4).unwrap();
+        let mut txt = io::BufReader::new("\n# comment\n".as_bytes());
+        assert_eq!(
+            WorkDay::parse(
+                &mut txt,
+                Some(date),
+                false,
+                false,
+                &KeyClasses::default(),
+                "tst_file"
+            ),
+            Ok(WorkDay {
+                date,
+                entries: Vec::new(),
+                additional_text: String::new(),
+            })
+        );
+        let mut txt = io::BufReader::new("".as_bytes());
+        assert_eq!(
+            WorkDay::parse(
+                &mut txt,
+                None,
+                false,
+                false,
+                &KeyClasses::default(),
+                "tst_file"
+            ),
+            Err(Error::MissingDate {
+                file: "tst_file".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_with_configured_break_keys() {
+        let txt: &str = r"-- 2018-05-04 Mo 12:27 -- Foo
+-- 2018-05-04 Mo 12:29 -- Lunch";
+        let key_classes = KeyClasses::new(vec!["Lunch".to_string()], Vec::new());
+        let mut stream = io::BufReader::new(txt.as_bytes());
+        assert!(WorkDay::parse(&mut stream, None, false, false, &key_classes, "tst_file").is_ok());
+        let mut stream = io::BufReader::new(txt.as_bytes());
+        assert_eq!(
+            WorkDay::parse(
+                &mut stream,
+                None,
+                false,
+                false,
+                &KeyClasses::default(),
+                "tst_file"
+            ),
+            Err(Error::MissingFinalPause {
+                file: "tst_file".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_worked_time_without_excluded_keys() {
+        let date = Date::from_ymd_opt(2018, 5, 4).unwrap();
+        let day = Day {
+            duration_of_day: chrono::Duration::hours(8),
+            required_time: required_time::RequiredTime {
+                date,
+                day_type: required_time::DayType::WorkDay,
+                required_time: chrono::Duration::hours(8),
+                line_nr: 0,
+            },
+            work_day: WorkDay {
+                date,
+                entries: vec![
+                    make_entry("Foo", 60),
+                    make_entry("Doctor", 30),
+                    make_entry("Break", 20),
+                    make_entry("Pause", 10),
+                ],
+                additional_text: String::new(),
+            },
+        };
+        let key_classes = KeyClasses::new(vec!["Break".to_string()], vec!["Doctor".to_string()]);
+        assert_eq!(key_classes.classify("Doctor"), KeyClass::Excluded);
+        assert_eq!(key_classes.classify("Break"), KeyClass::Break);
+        assert_eq!(day.worked_time(&key_classes), chrono::Duration::minutes(70));
+    }
+
     #[test]
     fn test_parse_missing_final_pause_in_lenient_mode() {
         let txt: &str = r"-- 2018-05-04 Mo 12:27 -- Foo
 -- 2018-05-04 Mo 12:29 -- Bar
 -- 2018-05-04 Mo 12:39 -- Baz";
         let mut txt = io::BufReader::new(txt.as_bytes());
-        let parsed_entries = WorkDay::parse(&mut txt, None, true, "tst_file");
+        let parsed_entries = WorkDay::parse(
+            &mut txt,
+            None,
+            true,
+            false,
+            &KeyClasses::default(),
+            "tst_file",
+        );
         assert!(parsed_entries.is_ok());
 
         let expected_entries = vec![
@@ -568,6 +920,7 @@ mod tests {
                 duration: chrono::Duration::minutes(2),
                 key: "Foo".to_string(),
                 sub_keys: Vec::new(),
+                alias: None,
                 raw_data: "-- 2018-05-04 Mo 12:27 -- Foo\n".to_string(),
             },
             Entry {
@@ -575,6 +928,7 @@ mod tests {
                 duration: chrono::Duration::minutes(10),
                 key: "Bar".to_string(),
                 sub_keys: Vec::new(),
+                alias: None,
                 raw_data: "-- 2018-05-04 Mo 12:29 -- Bar\n".to_string(),
             },
             Entry {
@@ -582,6 +936,7 @@ mod tests {
                 duration: chrono::Duration::minutes(0),
                 key: "Baz".to_string(),
                 sub_keys: Vec::new(),
+                alias: None,
                 raw_data: "-- 2018-05-04 Mo 12:39 -- Baz".to_string(),
             },
         ];
@@ -593,6 +948,118 @@ mod tests {
         assert_eq!(parsed_entries.unwrap(), expected);
     }
 
+    fn make_entry(key: &str, minutes: i64) -> Entry {
+        Entry {
+            start_ts: Time::from_hms_opt(12, 0, 0).unwrap(),
+            duration: chrono::Duration::minutes(minutes),
+            key: key.to_string(),
+            sub_keys: vec!["Foo".to_string()],
+            alias: None,
+            raw_data: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_in_progress() {
+        let txt: &str = r"-- 2018-05-04 Mo 12:27 -- Foo
+-- 2018-05-04 Mo 12:29 -- Bar";
+        let mut txt = io::BufReader::new(txt.as_bytes());
+        let work_day = WorkDay::parse(
+            &mut txt,
+            None,
+            false,
+            true,
+            &KeyClasses::default(),
+            "tst_file",
+        )
+        .unwrap();
+        assert_eq!(work_day.entries.len(), 2);
+        assert_eq!(work_day.entries[1].duration, chrono::Duration::zero());
+    }
+
+    #[test]
+    fn test_parse_file_in_progress() {
+        let dir = std::env::temp_dir().join(format!("log_work_test_parse_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("20180504_work.work");
+        std::fs::write(&file, "-- 2018-05-04 Mo 12:27 -- Foo\n").unwrap();
+        let date = Date::from_ymd_opt(2018, 5, 4).unwrap();
+        let key_classes = KeyClasses::default();
+
+        assert!(WorkDay::parse_file(&file, false, Some(date), &key_classes).is_ok());
+        // only the file of the day in progress may end on an open entry
+        assert_eq!(
+            WorkDay::parse_file(&file, false, date.succ_opt(), &key_classes),
+            Err(Error::MissingFinalPause {
+                file: file.to_string_lossy().to_string(),
+            })
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_split_entry_evenly() {
+        let entries = make_entry("PROJ-1|PROJ-2|PROJ-3", 100).split();
+        let expected = vec![
+            (
+                "PROJ-1".to_string(),
+                Time::from_hms_opt(12, 0, 0).unwrap(),
+                34,
+            ),
+            (
+                "PROJ-2".to_string(),
+                Time::from_hms_opt(12, 34, 0).unwrap(),
+                33,
+            ),
+            (
+                "PROJ-3".to_string(),
+                Time::from_hms_opt(13, 7, 0).unwrap(),
+                33,
+            ),
+        ];
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.key.clone(),
+                    entry.start_ts,
+                    entry.duration.num_minutes(),
+                )
+            })
+            .collect();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn test_split_entry_with_weights() {
+        let entries = make_entry("PROJ-1*2|PROJ-2", 90).split();
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.key.as_str(), entry.duration.num_minutes()))
+            .collect();
+        assert_eq!(entries, vec![("PROJ-1", 60), ("PROJ-2", 30)]);
+    }
+
+    #[test]
+    fn test_split_entry_with_single_key() {
+        let entry = make_entry("PROJ-1*2", 90);
+        assert_eq!(entry.split(), vec![entry.clone()]);
+    }
+
+    #[test]
+    fn test_compute_summary_with_multiple_keys() {
+        let work_day = WorkDay {
+            date: Date::from_ymd_opt(2018, 5, 4).unwrap(),
+            entries: vec![make_entry("A|B", 60), make_entry("A", 10)],
+            additional_text: String::new(),
+        };
+        let mut expected = Summary::new();
+        expected.insert("A".to_string(), chrono::Duration::minutes(40));
+        expected.insert("B".to_string(), chrono::Duration::minutes(30));
+        assert_eq!(work_day.compute_summary(), expected);
+    }
+
     #[test]
     fn test_parse_entries_line_with_empty_lines() {
         let txt: &str = r"-- 2018-05-04 Mo 12:27 -- Foo
@@ -604,7 +1071,14 @@ Hier kommt jetzt einfach nur noch geblubber
 ";
         let txt = txt.as_bytes();
         let mut txt = io::BufReader::new(txt);
-        let parsed_entries = WorkDay::parse(&mut txt, None, false, "tst_file");
+        let parsed_entries = WorkDay::parse(
+            &mut txt,
+            None,
+            false,
+            false,
+            &KeyClasses::default(),
+            "tst_file",
+        );
         assert!(parsed_entries.is_ok());
 
         let expected_entries = vec![
@@ -613,6 +1087,7 @@ Hier kommt jetzt einfach nur noch geblubber
                 duration: chrono::Duration::minutes(20),
                 key: "Foo".to_string(),
                 sub_keys: Vec::new(),
+                alias: None,
                 raw_data: "-- 2018-05-04 Mo 12:27 -- Foo\nBar Baz\n".to_string(),
             },
             Entry {
@@ -620,15 +1095,17 @@ Hier kommt jetzt einfach nur noch geblubber
                 duration: chrono::Duration::minutes(61),
                 key: "Bam".to_string(),
                 sub_keys: Vec::new(),
+                alias: None,
                 raw_data: "-- 2018-05-04 Mo 12:47 -- Bam\n".to_string(),
             },
             Entry {
                 start_ts: Time::from_hms_opt(13, 48, 0)
-                    .ok_or_else(|| Error::ParseTime)
+                    .ok_or(Error::ParseTime)
                     .unwrap(),
                 duration: chrono::Duration::minutes(0),
                 key: "Pause".to_string(),
                 sub_keys: vec!["Blah".to_string()],
+                alias: None,
                 raw_data: "-- 2018-05-04 Mo 13:48 -- Pause Blah\n".to_string(),
             },
         ];
@@ -0,0 +1,231 @@
+use super::work_day;
+use super::*;
+
+/// The name of the .work-files created by `add_entry` after their date
+pub const DEFAULT_NAME: &str = "work";
+
+/// The name of the .work-file of the date, e.g. '20180509_work.work'
+pub fn file_name(date: &Date, name: &str) -> String {
+    format!("{}_{}.work", date.format("%Y%m%d"), name)
+}
+
+/// Finds the .work-file of the date in the directory, i.e. a file named like '20180509_*.work'.
+/// If there are several, the first one in sorted order is returned.
+pub fn find_work_file(dir: &std::path::Path, date: &Date) -> Result<Option<std::path::PathBuf>> {
+    let prefix = format!("{}_", date.format("%Y%m%d"));
+    let mut files = Vec::new();
+    for dir_entry in std::fs::read_dir(dir)? {
+        let path = dir_entry?.path();
+        let matches = path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .is_some_and(|file_name| {
+                file_name.starts_with(&prefix) && file_name.ends_with(".work")
+            });
+        if matches && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files.into_iter().next())
+}
+
+/// Creates the empty .work-file of the date in the directory. Fails if there already is a
+/// .work-file for the date.
+pub fn create_work_file(
+    dir: &std::path::Path,
+    date: &Date,
+    name: &str,
+) -> Result<std::path::PathBuf> {
+    if name.is_empty() || name.contains(std::path::is_separator) {
+        return Err(Error::CommandLine(format!(
+            "Invalid name '{}' for a .work-file",
+            name
+        )));
+    }
+    if let Some(file) = find_work_file(dir, date)? {
+        return Err(Error::WorkFileExists { file });
+    }
+    let file = dir.join(file_name(date, name));
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&file)?;
+    Ok(file)
+}
+
+/// Formats the line of an entry like the vim plugin does, e.g. '-- 2018-05-09 Wed 07:25 -- Foo'
+pub fn format_entry(ts: &DateTime, description: &str) -> String {
+    format!("-- {} -- {}\n", ts.format("%Y-%m-%d %a %H:%M"), description)
+}
+
+/// Whether `WorkDay::parse` takes the line as part of the entries or of the free text. Empty lines
+/// and comments end the entries.
+fn is_content(line: &str) -> bool {
+    line != "\n" && !line.starts_with('#')
+}
+
+/// Inserts the line of an entry behind the entries of a .work-file, i.e. in front of the empty
+/// line separating them from the free text.
+pub fn insert_entry(content: &str, entry: &str) -> String {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let first = match lines.iter().position(|line| is_content(line)) {
+        Some(first) => first,
+        None => return format!("{}{}", content, entry),
+    };
+    let mut ret: String = lines[..first].concat();
+    if !lines[first].starts_with("-- ") {
+        // only free text, which has to be separated from the new entries
+        ret.push_str(entry);
+        ret.push('\n');
+        ret.push_str(&lines[first..].concat());
+        return ret;
+    }
+    let end = lines[first..]
+        .iter()
+        .position(|line| !is_content(line))
+        .map_or(lines.len(), |end| first + end);
+    ret.push_str(&lines[first..end].concat());
+    if !ret.ends_with('\n') {
+        ret.push('\n');
+    }
+    ret.push_str(entry);
+    ret.push_str(&lines[end..].concat());
+    ret
+}
+
+/// Adds an entry at `ts` to the .work-file of its date in the directory and returns the file and
+/// the line of the entry. The file is created if there is none. Fails without changing the file if
+/// it would become invalid, e.g. because the entry lies before the last one.
+pub fn add_entry(
+    dir: &std::path::Path,
+    ts: &DateTime,
+    description: &str,
+    key_classes: &work_day::KeyClasses,
+) -> Result<(std::path::PathBuf, String)> {
+    let date = ts.date();
+    let (file, content) = match find_work_file(dir, &date)? {
+        Some(file) => {
+            let content = std::fs::read_to_string(&file)?;
+            (file, content)
+        }
+        None => (dir.join(file_name(&date, DEFAULT_NAME)), String::new()),
+    };
+    let entry = format_entry(ts, description);
+    let content = insert_entry(&content, &entry);
+    // the day is still in progress, so it does not have to end on a break
+    work_day::WorkDay::parse(
+        &mut content.as_bytes(),
+        Some(date),
+        false,
+        true,
+        key_classes,
+        &file.to_string_lossy(),
+    )?;
+    std::fs::write(&file, content)?;
+    Ok((file, entry.trim_end().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_and_find_work_file() {
+        let dir = std::env::temp_dir().join(format!("log_work_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let date = Date::from_ymd_opt(2018, 5, 9).unwrap();
+
+        assert_eq!(find_work_file(&dir, &date).unwrap(), None);
+        let file = create_work_file(&dir, &date, "work").unwrap();
+        assert_eq!(file, dir.join("20180509_work.work"));
+        assert_eq!(find_work_file(&dir, &date).unwrap(), Some(file.clone()));
+        assert_eq!(
+            find_work_file(&dir, &Date::from_ymd_opt(2018, 5, 10).unwrap()).unwrap(),
+            None
+        );
+        assert_eq!(
+            create_work_file(&dir, &date, "other"),
+            Err(Error::WorkFileExists { file })
+        );
+        assert!(create_work_file(&dir, &date, "a/b").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_insert_entry() {
+        let entry = "-- 2018-05-09 Wed 12:00 -- Pause\n";
+        assert_eq!(insert_entry("", entry), entry);
+        assert_eq!(
+            insert_entry("# comment\n\n", entry),
+            format!("# comment\n\n{}", entry)
+        );
+        // the entries end with the file
+        assert_eq!(
+            insert_entry("-- 2018-05-09 Mi 08:00 -- Foo", entry),
+            format!("-- 2018-05-09 Mi 08:00 -- Foo\n{}", entry)
+        );
+        // the entries end with an empty line, which is followed by free text
+        assert_eq!(
+            insert_entry(
+                "\n-- 2018-05-09 Mi 08:00 -- Foo\n  continued\n\nSome text\n\n-- no entry\n",
+                entry
+            ),
+            format!(
+                "\n-- 2018-05-09 Mi 08:00 -- Foo\n  continued\n{}\nSome text\n\n-- no entry\n",
+                entry
+            )
+        );
+        // only free text
+        assert_eq!(
+            insert_entry("Some text\n", entry),
+            format!("{}\nSome text\n", entry)
+        );
+    }
+
+    #[test]
+    fn test_add_entry() {
+        let dir = std::env::temp_dir().join(format!("log_work_test_add_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let date = Date::from_ymd_opt(2018, 5, 9).unwrap();
+        let key_classes = work_day::KeyClasses::default();
+
+        let (file, line) = add_entry(
+            &dir,
+            &date.and_hms_opt(8, 0, 0).unwrap(),
+            "Foo bar",
+            &key_classes,
+        )
+        .unwrap();
+        assert_eq!(file, dir.join("20180509_work.work"));
+        assert_eq!(line, "-- 2018-05-09 Wed 08:00 -- Foo bar");
+        std::fs::write(&file, format!("{}\n\nSome text\n", line)).unwrap();
+        add_entry(
+            &dir,
+            &date.and_hms_opt(12, 0, 0).unwrap(),
+            "Pause",
+            &key_classes,
+        )
+        .unwrap();
+        // an entry before the last one is refused
+        assert_eq!(
+            add_entry(
+                &dir,
+                &date.and_hms_opt(11, 0, 0).unwrap(),
+                "Foo",
+                &key_classes,
+            ),
+            Err(Error::TimeNotMonotonic {
+                file: file.to_string_lossy().to_string(),
+                line_nr: 3,
+            })
+        );
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "-- 2018-05-09 Wed 08:00 -- Foo bar\n-- 2018-05-09 Wed 12:00 -- Pause\n\nSome text\n"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,32 +1,41 @@
 mod log_work;
 
+use chrono::Datelike;
+use chrono::Timelike;
 use structopt::StructOpt;
 
 use std::io::BufRead;
 
+/// The exit code if an error occurred
+const EXIT_CODE_ERROR: i32 = 1;
+/// The exit code if the user (or the confirmation policy) canceled the JIRA update
+const EXIT_CODE_CANCELED: i32 = 2;
+/// The exit code if the compliance check found violations of the working time law
+const EXIT_CODE_VIOLATIONS: i32 = 3;
+
 lazy_static::lazy_static! {
 static ref APP_INFO: directories::ProjectDirs =
     directories::ProjectDirs::from("de", "belgoking", "log_work").unwrap();
 }
 
-/** TODO
+/* TODO
  * Unittests for aggregating functions
  */
 
-fn parse_duration(s: &str) -> Result<chrono::Duration, log_work::Error> {
-    let re = regex::Regex::new(r"^((\d+)h)? ?((\d+)m)?$").expect("broken regular expression");
-    match re.captures(s) {
-        Some(c) => {
-            let h = c.get(2).map_or("0", |m| m.as_str());
-            let h = h.parse::<i64>()?;
-            let m = c.get(4).map_or("0", |m| m.as_str());
-            let m = m.parse::<i64>()?;
-            Ok(chrono::Duration::hours(h) + chrono::Duration::minutes(m))
-        }
-        None => Err(log_work::Error::CommandLine(
-            "Command line argument did not have the form '<hours>h <minutes>m'".to_string(),
-        )),
-    }
+fn parse_time(s: &str) -> Result<chrono::NaiveTime, log_work::Error> {
+    chrono::NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| {
+        log_work::Error::CommandLine(
+            "Command line argument did not have the form 'HH:MM'".to_string(),
+        )
+    })
+}
+
+fn parse_date(s: &str) -> Result<chrono::NaiveDate, log_work::Error> {
+    chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| {
+        log_work::Error::CommandLine(
+            "Command line argument did not have the form 'yyyy-mm-dd'".to_string(),
+        )
+    })
 }
 
 #[derive(Debug, structopt::StructOpt, Default)]
@@ -38,6 +47,10 @@ The format of the .work-files is:
 
 Some other text separated by the entries by an empty line.
 
+An entry can be split among several keys by joining them with '|' (e.g.
+'PROJ-1|PROJ-2'). The duration is split evenly or according to integer weights
+given after a '*' (e.g. 'PROJ-1*2|PROJ-2' logs 2/3 to PROJ-1).
+
 The format of the holidays-file is:
 yyyy-mm-dd -- [WKFUHÜ] description or
 yyyy-mm-dd--yyyy-mm-dd -- [WKFUHÜ] description
@@ -50,12 +63,29 @@ W - (Weiterbildung) Job Education/Job Travel. These days have an expected
 K - (Krank) Sick. Expected logged time is 0.
 F - (Feiertag) Holiday. Expected logged time is 0.
 U - (Urlaub) Vacation. Expected logged time is 0. This will be added to the
-    vacation count.
+    vacation count (see report --vacation_report).
 H - (Halber Tag Urlaub) Half day vacation. Expected logged time is 1/2 of a
-    day.
+    day. This will be added to the vacation count as 1/2 day.
 Ü - (Überstundenabbau) Reduction of overtime. Expected logged time is 1 day.
     This is just a marker, such that no warning regarding a missing day is
     generated.
+
+With --holiday_region the public holidays of the region are added as if they
+were given as F-lines. The events of the iCalendar files given with --ics are
+added according to the --ics_rule options as if they were lines of the
+holidays file (events spanning several days count as ranges).
+
+The options in front of the subcommand (e.g. 'log_work -H holidays.txt report
+-v *.work') configure log_work. They can also be put into the rc-file
+'log_work.rc' in the configuration directory, one per line. The options of the
+report (-v, -l, -f, -g, --csv_delimiter and --decimal_separator) may be given
+in front of or behind the subcommand as well as in the rc-file.
+
+Without a subcommand log_work prints the report like older versions did, i.e.
+'log_work *.work' is the same as 'log_work report *.work'. The .work-files given
+in the rc-file are added to the ones of 'report', 'check' and 'sync jira'.
+'log_work --log_to_jira *.work' of older versions is now 'log_work sync jira
+*.work', a '--log_to_jira' line in the rc-file is ignored.
 ")]
 #[structopt(setting = structopt::clap::AppSettings::ColoredHelp)]
 struct Opt {
@@ -63,53 +93,378 @@ struct Opt {
     #[structopt(short = "H", long = "holidays", parse(from_os_str))]
     holidays: Option<std::path::PathBuf>,
 
+    /// iCalendar files (.ics) with holidays and absences in addition to the holidays file. Their
+    /// events are mapped to day types by --ics_rule. Recurring events are not supported.
+    #[structopt(long = "ics", parse(from_os_str), number_of_values = 1)]
+    ics: Vec<std::path::PathBuf>,
+
+    /// Maps events of the iCalendar files to a day type of the holidays file, e.g. 'Urlaub -> U'
+    /// or '/.*[Kk]rank.*/ -> K' (the regex has to match the whole text). The rules are matched
+    /// against the categories and the summary of the events, the first matching rule wins and
+    /// events without a matching rule are ignored.
+    #[structopt(long = "ics_rule", number_of_values = 1)]
+    ics_rule: Vec<log_work::ics::IcsRule>,
+
+    /// Add the public holidays of the region (e.g. 'DE-BY', 'DE-BW' or 'AT', 'DE' only contains
+    /// the nationwide holidays) to the ones of the holidays file
+    #[structopt(long = "holiday_region")]
+    holiday_region: Option<log_work::public_holidays::Region>,
+
+    /// A file mapping keys to JIRA issues. Each line has the form 'Key -> ISSUE-1' or
+    /// '/regex/ -> ISSUE-1' (the regex has to match the whole key, the issue may refer to its
+    /// groups as '$1'). The first matching line wins and lines starting with '#' are ignored.
+    #[structopt(short = "a", long = "aliases", parse(from_os_str))]
+    aliases: Option<std::path::PathBuf>,
+
     /// Write debugging output
     #[structopt(short = "d", long = "debug")]
     debug: bool,
 
-    /// Print more details
-    #[structopt(short = "v", long = "verbose")]
+    /// Print more details in the report
+    #[structopt(short = "v", long = "verbose", global = true)]
     verbose: bool,
 
-    /// Don't abort on some errors. Especially don't abort if a day does not end on a pause.
-    #[structopt(short = "l", long = "lenient")]
+    /// Don't abort on some errors. Especially don't abort if a day does not end on a pause. Not
+    /// used by 'sync jira', which always reads the .work-files strictly.
+    #[structopt(short = "l", long = "lenient", global = true)]
     be_lenient: bool,
 
+    /// The output format of the report, either 'text' (default), 'json', 'csv' or 'tsv'. The
+    /// CSV and TSV formats contain one row per entry.
+    #[structopt(short = "f", long = "format", global = true)]
+    format: Option<log_work::report::OutputFormat>,
+
+    /// The field delimiter of the 'csv' format of the report (a single character or 'tab',
+    /// default ',')
+    #[structopt(
+        long = "csv_delimiter",
+        parse(try_from_str = parse_separator),
+        global = true
+    )]
+    csv_delimiter: Option<char>,
+
+    /// The decimal separator of the 'csv' and 'tsv' formats of the report (default '.')
+    #[structopt(
+        long = "decimal_separator",
+        parse(try_from_str = parse_separator),
+        global = true
+    )]
+    decimal_separator: Option<char>,
+
+    /// Instead of a summary per day let the report print a summary per 'week', 'month' or 'year'
+    #[structopt(short = "g", long = "group_by", global = true)]
+    group_by: Option<log_work::report::Period>,
+
+    /// Comma separated keys of breaks (default 'Pause'). Breaks don't count as work and each
+    /// day has to end on one.
+    #[structopt(long = "break_keys", use_delimiter = true)]
+    break_keys: Vec<String>,
+
+    /// Comma separated keys which are logged but don't count as work (e.g. 'Private,Doctor')
+    #[structopt(long = "excluded_keys", use_delimiter = true)]
+    excluded_keys: Vec<String>,
+
     /// The duration of a work-day matching the expressoin '(\d+h)? ?(\d+m)?' with the first part
     /// denominating the hours and the second part the minutes.
     #[structopt(
         short = "u",
         long = "duration_of_day",
-        parse(try_from_str = parse_duration)
+        parse(try_from_str = log_work::util::parse_duration)
     )]
     duration_of_day: Option<chrono::Duration>,
 
+    /// The required time per weekday, e.g. 'Mo-Th 8h, Fr 5h, Sa-Su 0' (weekdays not mentioned
+    /// are free). Prefixed with a date (e.g. '2023-07-01: Mo-Th 8h') the schedule is valid from
+    /// that date on, so the option may be given repeatedly for changes of the contract. Without
+    /// a schedule --duration_of_day is required from monday to friday, which otherwise only
+    /// serves as the length of a day when printing durations of several days.
+    #[structopt(long = "schedule", number_of_values = 1)]
+    schedule: Vec<log_work::schedule::DatedSchedule>,
+
+    /// The date from which on the flextime balance is computed (format 'yyyy-mm-dd'). Without
+    /// it the balance starts at the first given day.
+    #[structopt(long = "balance_start_date", parse(try_from_str = parse_date))]
+    balance_start_date: Option<chrono::NaiveDate>,
+
+    /// The flextime balance at the start date matching the expression '-?(\d+h)? ?(\d+m)?'
+    #[structopt(
+        long = "opening_balance",
+        parse(try_from_str = log_work::util::parse_duration),
+        allow_hyphen_values = true
+    )]
+    opening_balance: Option<chrono::Duration>,
+
+    /// The annual vacation entitlement in days. Prefixed with a year (e.g. '2024: 28') it is valid
+    /// from that year on, so the option may be given repeatedly.
+    #[structopt(long = "vacation_days", number_of_values = 1)]
+    vacation_days: Vec<log_work::vacation::DatedEntitlement>,
+
+    /// The vacation days carried over into the first year of the vacation account
+    #[structopt(long = "vacation_carry_over", allow_hyphen_values = true)]
+    vacation_carry_over: Option<f64>,
+
+    /// The day on which vacation carried over from the previous year expires (format 'mm-dd',
+    /// e.g. '03-31'). Without it carried over vacation never expires.
+    #[structopt(long = "carry_over_expiry")]
+    carry_over_expiry: Option<log_work::vacation::Expiry>,
+
+    /// The maximal work per day for 'check --compliance' and 'report --leave_time' (default
+    /// '10h')
+    #[structopt(long = "max_daily_work", parse(try_from_str = log_work::util::parse_duration))]
+    max_daily_work: Option<chrono::Duration>,
+
+    /// The break required after some work for 'check --compliance' and 'report --leave_time',
+    /// e.g. '6h: 30m' (default '6h: 30m' and '9h: 45m'). The option may be given repeatedly.
+    #[structopt(long = "break_rule", number_of_values = 1)]
+    break_rule: Vec<log_work::compliance::BreakRule>,
+
+    /// The maximal work without a break for 'check --compliance' (default '6h')
+    #[structopt(
+        long = "max_work_without_break",
+        parse(try_from_str = log_work::util::parse_duration)
+    )]
+    max_work_without_break: Option<chrono::Duration>,
+
+    /// The minimal duration of an interruption of the work to count as break for
+    /// 'check --compliance' and 'report --leave_time' (default '15m')
+    #[structopt(long = "min_break", parse(try_from_str = log_work::util::parse_duration))]
+    min_break: Option<chrono::Duration>,
+
+    /// The minimal rest between the work of two days for 'check --compliance' (default '11h')
+    #[structopt(long = "min_rest", parse(try_from_str = log_work::util::parse_duration))]
+    min_rest: Option<chrono::Duration>,
+
+    /// Don't report work on sundays and holidays in 'check --compliance' (e.g. if an exception of
+    /// the working time law applies)
+    #[structopt(long = "allow_rest_day_work")]
+    allow_rest_day_work: bool,
+
+    /// The directory of the .work-files, in which 'new', 'start' and 'pause' create or change
+    /// today's file (default: the current directory)
+    #[structopt(long = "work_dir", parse(from_os_str))]
+    work_dir: Option<std::path::PathBuf>,
+
     /// Timezone in the format `Europe/Berlin` (usually this is not needed. However, Jira requires
-    /// timezones for time logging)
+    /// timezones for time logging and the UTC times of the iCalendar files are converted to it)
     #[structopt(short = "z")]
     timezone: Option<chrono_tz::Tz>,
 
-    /// Log the times of the days to the configured JIRA server
-    #[structopt(long = "log_to_jira")]
-    log_to_jira: bool,
-
     /// The base URL of the JIRA server (e.g. 'https://jira.example.com/jira')
     #[structopt(long = "jira_base_url")]
     jira_base_url: Option<String>,
 
+    /// The kind of the JIRA server, either 'server' (default, REST API v2) or 'cloud' (REST API
+    /// v3). For 'cloud' use your email address as username and an API token as password.
+    #[structopt(long = "jira_flavor")]
+    jira_flavor: Option<log_work::jira::JiraFlavor>,
+
     /// The username of the JIRA user
     #[structopt(long = "jira_username")]
     jira_username: Option<String>,
 
-    /// The password of the JIRA user
-    #[structopt(long = "jira_password")]
-    jira_password: Option<String>,
+    /// The password of the JIRA user. Prefer the environment variable, --jira_password_command
+    /// or a ~/.netrc entry for the host of the JIRA server, as command line arguments end up in
+    /// the shell history.
+    #[structopt(
+        long = "jira_password",
+        env = "LOG_WORK_JIRA_PASSWORD",
+        hide_env_values = true
+    )]
+    jira_password: Option<log_work::credentials::Secret>,
+
+    /// A command printing the password of the JIRA user, e.g. 'pass show jira'. Like the password
+    /// its output is sent as basic authentication, a personal access token has to be given by
+    /// --jira_token.
+    #[structopt(long = "jira_password_command")]
+    jira_password_command: Option<String>,
+
+    /// A personal access token of the JIRA user. It is used instead of the password if given.
+    #[structopt(
+        long = "jira_token",
+        env = "LOG_WORK_JIRA_TOKEN",
+        hide_env_values = true
+    )]
+    jira_token: Option<log_work::credentials::Secret>,
+
+    /// The .work-files to read in addition to the ones of the subcommand, without a subcommand
+    /// the ones of the report
+    #[structopt(parse(from_os_str))]
+    files: Vec<std::path::PathBuf>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, structopt::StructOpt)]
+enum Command {
+    /// Print summaries of the worked time of the .work-files or one of the other reports. Exits
+    /// with 1 if the files could not be read.
+    Report(ReportOpt),
+    /// Only validate the .work-files as well as the holidays, iCalendar and alias files. Exits with
+    /// 1 if any of them has errors and with 3 if --compliance finds violations.
+    Check(CheckOpt),
+    /// Upload the worked time of the .work-files
+    Sync {
+        #[structopt(subcommand)]
+        target: SyncTarget,
+    },
+    /// Create today's .work-file in the --work_dir and print its path. Exits with 1 if there
+    /// already is a .work-file for today.
+    New(NewOpt),
+    /// Add an entry starting the work on a key to today's .work-file in the --work_dir. The file
+    /// is created if there is none. Exits with 1 if the entry would make the file invalid, e.g.
+    /// because it lies before the last entry.
+    Start(StartOpt),
+    /// Add a break (the first of the --break_keys) to today's .work-file in the --work_dir like
+    /// 'start' does
+    Pause(PauseOpt),
+    /// Print the open entry of today's .work-file in the --work_dir, the time logged per key so
+    /// far and the required time left. Exits with 1 if there is no valid .work-file for today.
+    Status(StatusOpt),
+}
+
+#[derive(Debug, Default, structopt::StructOpt)]
+struct ReportOpt {
+    /// Instead of the worked time print the vacation taken, planned and remaining per year
+    /// according to the holidays file. The account starts with the first year with vacation,
+    /// but not before the first year given to --vacation_days if all of them have a year.
+    #[structopt(long = "vacation_report")]
+    vacation_report: bool,
+
+    /// Instead of the worked time print the absences according to the holidays file per type and
+    /// per month as well as the periods of absence. Half days count as 1/2 day.
+    #[structopt(long = "absence_report")]
+    absence_report: bool,
+
+    /// The first day of the absence report (format 'yyyy-mm-dd', default: the first day of the
+    /// current year)
+    #[structopt(long = "from_date", parse(try_from_str = parse_date))]
+    from_date: Option<chrono::NaiveDate>,
+
+    /// The last day of the absence report (format 'yyyy-mm-dd', default: the last day of the
+    /// current year)
+    #[structopt(long = "to_date", parse(try_from_str = parse_date))]
+    to_date: Option<chrono::NaiveDate>,
+
+    /// Instead of the worked time write the entries of the holidays file as all-day events to
+    /// the given iCalendar file (a range of days becomes one event)
+    #[structopt(long = "export_ics", parse(from_os_str))]
+    export_ics: Option<std::path::PathBuf>,
+
+    /// What the summaries of the exported events reveal, either 'full' (default, day type and
+    /// description), 'type' (only the day type) or 'busy' (only 'Absent')
+    #[structopt(long = "ics_privacy")]
+    ics_privacy: Option<log_work::ics::Privacy>,
+
+    /// Put in front of the summaries of the exported events, e.g. 'Alice: '
+    #[structopt(long = "ics_summary_prefix")]
+    ics_summary_prefix: Option<String>,
+
+    /// Instead of the worked time print when today's work may end. The last entry of today's
+    /// .work-file is taken as running until now (so only this file may end without a break).
+    /// Shown are the earliest times at which the required time is worked and at which the
+    /// flextime balance is zero, both including the breaks required by --break_rule that are
+    /// still missing. The latter is limited by --max_daily_work.
+    #[structopt(long = "leave_time")]
+    leave_time: bool,
+
+    /// The .work-files
+    #[structopt(parse(from_os_str))]
+    files: Vec<std::path::PathBuf>,
+}
+
+#[derive(Debug, structopt::StructOpt)]
+struct CheckOpt {
+    /// Also list the violations of the working time law (by default the German
+    /// Arbeitszeitgesetz): too long days, missing breaks, too long work without a break, too
+    /// short rests between two days and work on sundays or holidays (unless
+    /// --allow_rest_day_work is given)
+    #[structopt(long = "compliance")]
+    compliance: bool,
 
     /// The .work-files
     #[structopt(parse(from_os_str))]
     files: Vec<std::path::PathBuf>,
 }
 
+#[derive(Debug, structopt::StructOpt)]
+enum SyncTarget {
+    /// Log the times of the days to the configured JIRA server. Exits with 1 on errors and with 2
+    /// if the update was canceled.
+    Jira(SyncJiraOpt),
+}
+
+#[derive(Debug, structopt::StructOpt)]
+struct SyncJiraOpt {
+    /// Only show which worklogs would be created, updated, deleted or skipped without changing
+    /// anything on the JIRA server
+    #[structopt(long = "dry_run", alias = "dry-run")]
+    dry_run: bool,
+
+    /// Update and delete already logged worklogs without asking
+    #[structopt(short = "y", long = "yes", conflicts_with = "no-replace")]
+    yes: bool,
+
+    /// Never update or delete already logged worklogs, only create the missing ones (does not ask
+    /// either). Entries overlapping a kept worklog of the same issue are not created.
+    #[structopt(long = "no_replace", alias = "no-replace")]
+    no_replace: bool,
+
+    /// The .work-files
+    #[structopt(parse(from_os_str))]
+    files: Vec<std::path::PathBuf>,
+}
+
+#[derive(Debug, structopt::StructOpt)]
+struct StartOpt {
+    /// The time of the entry (format 'HH:MM', default: now)
+    #[structopt(long = "at", parse(try_from_str = parse_time))]
+    at: Option<chrono::NaiveTime>,
+
+    /// The key to work on, e.g. 'PROJ-1'
+    key: String,
+
+    /// The sub keys or a description of the work
+    sub_keys: Vec<String>,
+}
+
+#[derive(Debug, structopt::StructOpt)]
+struct PauseOpt {
+    /// The time of the break (format 'HH:MM', default: now)
+    #[structopt(long = "at", parse(try_from_str = parse_time))]
+    at: Option<chrono::NaiveTime>,
+}
+
+#[derive(Debug, structopt::StructOpt)]
+struct StatusOpt {
+    /// Print a single line for shell prompts and status bars (e.g. tmux or i3blocks)
+    #[structopt(short = "s", long = "short")]
+    short: bool,
+}
+
+#[derive(Debug, structopt::StructOpt)]
+struct NewOpt {
+    /// The name of the file after the date, i.e. the file is named 'yyyymmdd_<name>.work'
+    #[structopt(default_value = "work")]
+    name: String,
+}
+
+fn parse_separator(s: &str) -> Result<char, log_work::Error> {
+    match s {
+        "tab" | "\\t" | "\t" => Ok('\t'),
+        _ => {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                _ => Err(log_work::Error::CommandLine(format!(
+                    "Expected a single character or 'tab' as separator, got '{}'",
+                    s
+                ))),
+            }
+        }
+    }
+}
+
 fn first_available<T>(opt1: Option<T>, opt2: Option<T>) -> Option<T> {
     match opt1 {
         Some(v) => Some(v),
@@ -117,59 +472,412 @@ fn first_available<T>(opt1: Option<T>, opt2: Option<T>) -> Option<T> {
     }
 }
 
-fn main() {
-    let mut opt_from_file = {
-        let mut rc_file = APP_INFO.config_dir().to_path_buf();
-        rc_file.push("log_work.rc");
-        println!("Application directory: {:?}", rc_file);
-        if let Ok(f) = std::fs::File::open(rc_file) {
-            let mut lines: Vec<String> = std::io::BufReader::new(f)
-                .lines()
-                .map(|e| e.unwrap())
-                .filter(|l| !l.is_empty())
-                .collect();
-            lines.insert(0, "DUMMY".to_string()); // normally the first element holds the program name
-            Opt::from_iter(lines.iter())
-        } else {
-            Opt::default()
+fn first_non_empty<T>(v1: Vec<T>, v2: Vec<T>) -> Vec<T> {
+    if v1.is_empty() {
+        v2
+    } else {
+        v1
+    }
+}
+
+fn print_text_report(
+    days: &log_work::work_day::Days,
+    opt: &Opt,
+    key_classes: &log_work::work_day::KeyClasses,
+    mut flextime_account: log_work::balance::FlextimeAccount,
+    duration_of_day: chrono::Duration,
+) {
+    match opt.group_by {
+        Some(period) => {
+            for mut group in
+                log_work::report::group_days(&days.days, period, duration_of_day, key_classes)
+            {
+                group.book_days(&mut flextime_account);
+                println!("{}", group);
+            }
+        }
+        None => {
+            for day in &days.days {
+                println!(
+                    "{}",
+                    log_work::work_day::DaySummary {
+                        day,
+                        key_classes,
+                        verbose: opt.verbose,
+                        balance: flextime_account.book_day(day, key_classes),
+                    }
+                );
+            }
         }
+    }
+    let mut summary = log_work::work_day::Summary::new();
+    let mut sum_required = chrono::Duration::hours(0);
+    for day in &days.days {
+        log_work::work_day::WorkDay::merge_summaries_right_into_left(
+            &mut summary,
+            &day.work_day.compute_summary(),
+        );
+        sum_required += day.required_time.required_time;
+    }
+    if days.days.len() > 1 {
+        println!("= Summary for all days:");
+        let alias_sources = log_work::alias::AliasSources::from_entries(
+            days.days.iter().flat_map(|day| day.work_day.entries.iter()),
+        );
+        let mut sum = chrono::Duration::hours(0);
+        for (key, duration) in summary.iter() {
+            println!(
+                "{:20}:{:>20}{}",
+                key,
+                log_work::util::WorkDuration {
+                    duration_of_day,
+                    duration: *duration
+                },
+                alias_sources.describe(key)
+            );
+            if key_classes.is_work(key) {
+                sum += *duration;
+            }
+        }
+        println!(
+            "{:20}: {:>20}",
+            " == Required ==",
+            log_work::util::WorkDuration {
+                duration: sum_required,
+                duration_of_day
+            }
+        );
+        println!(
+            "{:20}: {:>20}",
+            " == Total ==",
+            log_work::util::WorkDuration {
+                duration_of_day,
+                duration: sum
+            }
+        );
+    }
+    println!(
+        "{:20}: {:>20}",
+        " == Balance ==",
+        log_work::util::WorkDuration {
+            duration_of_day,
+            duration: flextime_account.balance()
+        }
+    );
+}
+
+fn print_json_report(
+    days: &log_work::work_day::Days,
+    opt: &Opt,
+    flextime_account: log_work::balance::FlextimeAccount,
+    duration_of_day: chrono::Duration,
+) {
+    let key_classes = key_classes(opt);
+    let periods = match opt.group_by {
+        Some(period) => {
+            let mut period_flextime_account = flextime_account.clone();
+            log_work::report::group_days(&days.days, period, duration_of_day, &key_classes)
+                .iter_mut()
+                .map(|group| {
+                    group.book_days(&mut period_flextime_account);
+                    group.to_json()
+                })
+                .collect()
+        }
+        None => Vec::new(),
     };
-    let opt_from_args = Opt::from_args();
+    let mut all_days = log_work::report::PeriodSummary {
+        label: String::new(),
+        days: days.days.iter().collect(),
+        duration_of_day,
+        key_classes: &key_classes,
+        balance: None,
+    };
+    let mut day_flextime_account = flextime_account.clone();
+    let json_days = days
+        .days
+        .iter()
+        .map(|day| log_work::report::JsonDay {
+            day,
+            balance: day_flextime_account.book_day(day, &key_classes),
+        })
+        .collect();
+    let mut all_days_flextime_account = flextime_account;
+    all_days.book_days(&mut all_days_flextime_account);
+    let report = log_work::report::JsonReport {
+        days: json_days,
+        periods,
+        summary: all_days.to_json_summary(),
+    };
+    println!(
+        "{}",
+        serde_json::to_string(&report).expect("Failed to serialize the report")
+    );
+}
 
-    if opt_from_args.debug || opt_from_file.debug {
-        println!("file={:?} cmd={:?}", opt_from_file, opt_from_args);
+fn print_csv_report(days: &log_work::work_day::Days, opt: &Opt, delimiter: char) {
+    let config = log_work::csv::CsvConfig {
+        delimiter,
+        decimal_separator: opt.decimal_separator.unwrap_or('.'),
+    };
+    log_work::csv::write_entries(&mut std::io::stdout(), &days.days, &config)
+        .expect("Failed to write the CSV export");
+}
+
+/// The timezone given by -z or else the local one
+fn timezone(opt: &Opt) -> log_work::jira::TimeZone {
+    if let Some(tz) = opt.timezone {
+        log_work::jira::TimeZone::Tz(tz)
+    } else {
+        log_work::jira::TimeZone::Local(chrono::Local)
     }
-    let mut files = opt_from_args.files;
-    files.append(&mut opt_from_file.files);
-    let opt = Opt {
-        holidays: first_available(opt_from_args.holidays, opt_from_file.holidays),
-        debug: opt_from_args.debug || opt_from_file.debug,
-        verbose: opt_from_args.verbose || opt_from_file.verbose,
-        be_lenient: opt_from_args.be_lenient || opt_from_file.be_lenient,
-        duration_of_day: first_available(
-            opt_from_args.duration_of_day,
-            opt_from_file.duration_of_day,
-        ),
-        timezone: first_available(opt_from_args.timezone, opt_from_file.timezone),
-        log_to_jira: opt_from_args.log_to_jira, // here we actually ignore the options from the file
-        jira_base_url: first_available(opt_from_args.jira_base_url, opt_from_file.jira_base_url),
-        jira_username: first_available(opt_from_args.jira_username, opt_from_file.jira_username),
-        jira_password: first_available(opt_from_args.jira_password, opt_from_file.jira_password),
-        files,
+}
+
+/// Reads the holidays file and the iCalendar files
+fn read_day_type_files(opt: &Opt) -> Vec<log_work::required_time::DayTypeEntry> {
+    let mut day_type_entries = match opt.holidays.clone() {
+        Some(fp) => log_work::required_time::parse_required_time_file(&fp)
+            .expect("Error parsing required time file"),
+        None => Vec::new(),
     };
+    for fp in &opt.ics {
+        day_type_entries.append(
+            &mut log_work::ics::parse_ics_file(fp, &opt.ics_rule, &timezone(opt))
+                .expect("Error parsing iCalendar file"),
+        );
+    }
+    day_type_entries
+}
 
-    if opt.debug {
-        println!("opt={:?}", opt);
+/// Adds the public holidays of the region between start_date and end_date to the entries
+fn add_public_holidays(
+    opt: &Opt,
+    day_type_entries: &mut Vec<log_work::required_time::DayTypeEntry>,
+    start_date: &chrono::NaiveDate,
+    end_date: &chrono::NaiveDate,
+) {
+    if let Some(ref region) = opt.holiday_region {
+        // after the entries of the file, such that their names take precedence
+        day_type_entries.append(
+            &mut log_work::public_holidays::public_holidays(region, start_date, end_date)
+                .expect("Failed to compute the public holidays"),
+        );
+    }
+}
+
+/// Reads the holidays file and the iCalendar files and adds the public holidays between start_date
+/// and end_date
+fn read_day_type_entries(
+    opt: &Opt,
+    start_date: &chrono::NaiveDate,
+    end_date: &chrono::NaiveDate,
+) -> Vec<log_work::required_time::DayTypeEntry> {
+    let mut day_type_entries = read_day_type_files(opt);
+    add_public_holidays(opt, &mut day_type_entries, start_date, end_date);
+    day_type_entries
+}
+
+fn print_vacation_report(opt: &Opt, schedules: &log_work::schedule::ScheduleSeries) {
+    if opt.vacation_days.is_empty() {
+        println!("ERROR: The vacation report requires --vacation_days");
+        std::process::exit(EXIT_CODE_ERROR);
+    }
+    let config = log_work::vacation::VacationConfig {
+        entitlements: opt.vacation_days.clone(),
+        opening_carry_over: opt.vacation_carry_over.unwrap_or(0.0),
+        carry_over_expiry: opt.carry_over_expiry,
+    };
+    let today = chrono::Local::now().date_naive();
+    // the range of the public holidays is only known after reading the holidays file
+    let mut day_type_entries = read_day_type_files(opt);
+    let years = config.years(&day_type_entries, &today);
+    add_public_holidays(
+        opt,
+        &mut day_type_entries,
+        &chrono::NaiveDate::from_ymd_opt(*years.start(), 1, 1).expect("Invalid year"),
+        &chrono::NaiveDate::from_ymd_opt(*years.end(), 12, 31).expect("Invalid year"),
+    );
+    let account = log_work::vacation::compute_vacation_account(
+        &day_type_entries,
+        years,
+        schedules,
+        &config,
+        &today,
+    )
+    .expect("Failed to compute the vacation account");
+    for vacation_year in account {
+        println!("{}", vacation_year);
+    }
+}
+
+fn print_absence_report(
+    opt: &Opt,
+    report_opt: &ReportOpt,
+    schedules: &log_work::schedule::ScheduleSeries,
+) {
+    let year = chrono::Local::now().year();
+    let start_date = report_opt
+        .from_date
+        .unwrap_or_else(|| chrono::NaiveDate::from_ymd_opt(year, 1, 1).expect("Invalid year"));
+    let end_date = report_opt
+        .to_date
+        .unwrap_or_else(|| chrono::NaiveDate::from_ymd_opt(year, 12, 31).expect("Invalid year"));
+    let required_time = log_work::required_time::consolidate_required_time(
+        &read_day_type_entries(opt, &start_date, &end_date),
+        &start_date,
+        &end_date,
+        schedules,
+    )
+    .expect("Failed to consolidate required times");
+    match log_work::absence::compute_absence_report(&required_time, schedules) {
+        Some(report) => println!("{}", report),
+        None => println!("The date range is empty"),
     }
-    let work_days_raw = log_work::work_day::Days::parse_work_files(opt.files, opt.be_lenient);
+}
+
+fn export_ics(opt: &Opt, report_opt: &ReportOpt, file_name: &std::path::PathBuf) {
+    let holidays = match opt.holidays {
+        Some(ref fp) => fp,
+        None => {
+            println!("ERROR: The iCalendar export requires a holidays file");
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+    };
+    let entries = log_work::required_time::parse_required_time_file(holidays)
+        .expect("Error parsing required time file");
+    let config = log_work::ics::IcsExportConfig {
+        privacy: report_opt
+            .ics_privacy
+            .unwrap_or(log_work::ics::Privacy::Full),
+        summary_prefix: report_opt.ics_summary_prefix.clone().unwrap_or_default(),
+    };
+    log_work::ics::write_ics_file(
+        file_name,
+        &entries,
+        &config,
+        &chrono::Utc::now().naive_utc(),
+    )
+    .expect("Failed to write the iCalendar file");
+}
+
+fn compliance_config(opt: &Opt) -> log_work::compliance::ComplianceConfig {
+    let default = log_work::compliance::ComplianceConfig::default();
+    log_work::compliance::ComplianceConfig {
+        max_daily_work: opt.max_daily_work.unwrap_or(default.max_daily_work),
+        break_rules: first_non_empty(opt.break_rule.clone(), default.break_rules),
+        max_work_without_break: opt
+            .max_work_without_break
+            .unwrap_or(default.max_work_without_break),
+        min_break: opt.min_break.unwrap_or(default.min_break),
+        min_rest: opt.min_rest.unwrap_or(default.min_rest),
+        check_rest_days: !opt.allow_rest_day_work,
+    }
+}
+
+/// Prints the violations of the working time law and returns their number
+fn print_compliance_report(
+    days: &log_work::work_day::Days,
+    files: &std::collections::BTreeMap<chrono::NaiveDate, String>,
+    opt: &Opt,
+) -> usize {
+    let violations = log_work::compliance::check_days(
+        &days.days,
+        files,
+        &key_classes(opt),
+        &compliance_config(opt),
+    );
+    for violation in &violations {
+        println!("{}", violation);
+    }
+    println!("{} violation(s) found", violations.len());
+    violations.len()
+}
+
+fn print_leave_time(
+    days: &log_work::work_day::Days,
+    opt: &Opt,
+    mut flextime_account: log_work::balance::FlextimeAccount,
+) {
+    let now = chrono::Local::now().naive_local();
+    let today = now.date();
+    let key_classes = key_classes(opt);
+    for day in days.days.iter().filter(|day| day.work_day.date < today) {
+        flextime_account.book_day(day, &key_classes);
+    }
+    let projection = days
+        .days
+        .iter()
+        .find(|day| day.work_day.date == today)
+        .and_then(|day| {
+            log_work::projection::project_leave_time(
+                day,
+                &key_classes,
+                &now,
+                flextime_account.balance(),
+                &compliance_config(opt),
+            )
+        });
+    match projection {
+        Some(projection) => println!("{}", projection),
+        None => {
+            println!("ERROR: No entries for today ({})", today.format("%F"));
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+    }
+}
+
+fn key_classes(opt: &Opt) -> log_work::work_day::KeyClasses {
+    if opt.break_keys.is_empty() {
+        log_work::work_day::KeyClasses::new(vec!["Pause".to_string()], opt.excluded_keys.clone())
+    } else {
+        log_work::work_day::KeyClasses::new(opt.break_keys.clone(), opt.excluded_keys.clone())
+    }
+}
+
+fn schedules(opt: &Opt) -> log_work::schedule::ScheduleSeries {
+    log_work::schedule::ScheduleSeries::new(
+        log_work::schedule::Schedule::from_duration_of_day(
+            opt.duration_of_day
+                .unwrap_or_else(|| chrono::Duration::hours(8)),
+        ),
+        opt.schedule.clone(),
+    )
+}
+
+/// Reads the .work-files, applies the aliases and joins the days with their required time. The
+/// file of the date `in_progress` may end on an open entry. Also returns the files by the date of
+/// their day. Prints the errors and returns `None` if any of the files could not be read.
+fn read_days(
+    opt: &Opt,
+    files: &[std::path::PathBuf],
+    be_lenient: bool,
+    in_progress: Option<chrono::NaiveDate>,
+) -> Option<(
+    log_work::work_day::Days,
+    std::collections::BTreeMap<chrono::NaiveDate, String>,
+)> {
+    let key_classes = key_classes(opt);
+    let schedules = schedules(opt);
+    let work_days_raw = log_work::work_day::Days::parse_work_files(
+        files.to_vec(),
+        be_lenient,
+        in_progress,
+        &key_classes,
+    );
     if work_days_raw.is_empty() {
         println!("No days given, aborting!");
-        return;
+        return None;
     }
 
+    let alias_table = match opt.aliases {
+        Some(ref fp) => log_work::alias::parse_alias_file(fp).expect("Error parsing alias file"),
+        None => log_work::alias::AliasTable::default(),
+    };
+
+    let mut file_by_date = std::collections::BTreeMap::new();
     let mut has_error = false;
     let mut work_day_by_date = std::collections::BTreeMap::new();
-    for ref day_raw in &work_days_raw {
+    for (file, ref day_raw) in &work_days_raw {
         if opt.debug {
             println!("Day: {:?}", day_raw);
         }
@@ -178,53 +886,41 @@ fn main() {
             has_error = true;
             continue;
         }
-        let day_raw = day_raw.as_ref().unwrap();
-        if let Some(ref e) = work_day_by_date.insert(day_raw.date, (*day_raw).clone()) {
-            println!("Duplicate day: {:?}", e);
+        let mut day = day_raw.as_ref().unwrap().clone();
+        alias_table.apply_to_day(&mut day, &key_classes);
+        if let Some(first_file) = file_by_date.get(&day.date) {
+            println!(
+                "Duplicate day {} in {:?} and {:?}",
+                day.date.format("%F"),
+                first_file,
+                file
+            );
             has_error = true;
+            continue;
         }
+        file_by_date.insert(day.date, file.to_string_lossy().to_string());
+        work_day_by_date.insert(day.date, day);
     }
     if has_error {
         println!("Abort because of errors");
-        return;
+        return None;
     };
-    let first_date = &work_days_raw[0].as_ref().unwrap().date;
-    let (min_day, max_day) =
-        work_days_raw
-            .iter()
-            .fold((*first_date, *first_date), |(min, max), day| {
-                let date = &day.as_ref().unwrap().date;
-                (std::cmp::min(min, *date), std::cmp::max(max, *date))
-            });
+    let min_day = *work_day_by_date.keys().next().expect("Missing days");
+    let max_day = *work_day_by_date.keys().next_back().expect("Missing days");
     if opt.debug {
         println!("min={} max={}", min_day.format("%F"), max_day.format("%F"));
     }
-    let duration_of_day = if let Some(d) = opt.duration_of_day {
-        d
-    } else {
-        chrono::Duration::hours(8)
-    };
-    let required_time = match opt.holidays {
-        Some(fp) => {
-            let required_time = log_work::required_time::parse_required_time_file(&fp)
-                .expect("Error parsing required time file");
-            log_work::required_time::consolidate_required_time(
-                &required_time,
-                &min_day,
-                &max_day,
-                &duration_of_day,
-            )
-            .expect("Failed to consolidate required times")
-        }
-        None => {
-            let empty = Vec::new();
-            log_work::required_time::consolidate_required_time(
-                &empty,
-                &min_day,
-                &max_day,
-                &duration_of_day,
-            )
-            .expect("Failed to consolidate required times")
+    let day_type_entries = read_day_type_entries(opt, &min_day, &max_day);
+    let required_time = match log_work::required_time::consolidate_required_time(
+        &day_type_entries,
+        &min_day,
+        &max_day,
+        &schedules,
+    ) {
+        Ok(required_time) => required_time,
+        Err(e) => {
+            println!("ERROR: Failed to consolidate required times: {}", e);
+            return None;
         }
     };
     if opt.debug {
@@ -234,96 +930,402 @@ fn main() {
     let days = log_work::work_day::Days::join_work_and_requirement(
         &work_day_by_date,
         &required_time,
-        &duration_of_day,
+        &schedules,
     );
     if opt.debug {
         for ref day in &days.days {
             println!("Required-times for {:?}: {:?}", day.required_time.date, day);
         }
     }
+    Some((days, file_by_date))
+}
 
-    let mut summary = log_work::work_day::Summary::new();
-    let mut sum_required = chrono::Duration::hours(0);
-    for day in &days.days {
-        println!(
-            "{}",
-            log_work::work_day::DaySummary {
-                day,
-                verbose: opt.verbose
-            }
-        );
-        log_work::work_day::WorkDay::merge_summaries_right_into_left(
-            &mut summary,
-            &day.work_day.compute_summary(),
-        );
-        sum_required += day.required_time.required_time;
+fn report(opt: &Opt, report_opt: &ReportOpt) {
+    let schedules = schedules(opt);
+    if report_opt.vacation_report {
+        print_vacation_report(opt, &schedules);
+        return;
     }
-    if days.days.len() > 1 {
-        println!("= Summary for all days:");
-        let mut sum = chrono::Duration::hours(0);
-        for (key, duration) in summary.iter() {
+    if report_opt.absence_report {
+        print_absence_report(opt, report_opt, &schedules);
+        return;
+    }
+    if let Some(ref fp) = report_opt.export_ics {
+        export_ics(opt, report_opt, fp);
+        return;
+    }
+    // today's file is still open while projecting the leave time
+    let in_progress = if report_opt.leave_time {
+        Some(chrono::Local::now().date_naive())
+    } else {
+        None
+    };
+    let days = match read_days(
+        opt,
+        &work_files(opt, &report_opt.files),
+        opt.be_lenient,
+        in_progress,
+    ) {
+        Some((days, _)) => days,
+        None => std::process::exit(EXIT_CODE_ERROR),
+    };
+    // the unit of durations spanning several days
+    let duration_of_day = opt
+        .duration_of_day
+        .unwrap_or_else(|| schedules.longest_day());
+    let flextime_account = log_work::balance::FlextimeAccount::new(
+        opt.balance_start_date,
+        opt.opening_balance
+            .unwrap_or_else(|| chrono::Duration::hours(0)),
+    );
+    if report_opt.leave_time {
+        print_leave_time(&days, opt, flextime_account);
+        return;
+    }
+    match opt.format {
+        Some(log_work::report::OutputFormat::Json) => {
+            print_json_report(&days, opt, flextime_account, duration_of_day)
+        }
+        Some(log_work::report::OutputFormat::Csv) => {
+            print_csv_report(&days, opt, opt.csv_delimiter.unwrap_or(','))
+        }
+        Some(log_work::report::OutputFormat::Tsv) => print_csv_report(&days, opt, '\t'),
+        Some(log_work::report::OutputFormat::Text) | None => print_text_report(
+            &days,
+            opt,
+            &key_classes(opt),
+            flextime_account,
+            duration_of_day,
+        ),
+    }
+}
+
+/// The .work-files of the subcommand followed by the ones given in front of it or in the rc-file
+fn work_files(opt: &Opt, files: &[std::path::PathBuf]) -> Vec<std::path::PathBuf> {
+    files.iter().chain(&opt.files).cloned().collect()
+}
+
+fn check(opt: &Opt, check_opt: &CheckOpt) {
+    let mut has_error = false;
+    if let Some(ref fp) = opt.holidays {
+        if let Err(e) = log_work::required_time::parse_required_time_file(fp) {
+            println!("ERROR: {}", e);
+            has_error = true;
+        }
+    }
+    for fp in &opt.ics {
+        if let Err(e) = log_work::ics::parse_ics_file(fp, &opt.ics_rule, &timezone(opt)) {
+            println!("ERROR: {}", e);
+            has_error = true;
+        }
+    }
+    if let Some(ref fp) = opt.aliases {
+        if let Err(e) = log_work::alias::parse_alias_file(fp) {
+            println!("ERROR: {}", e);
+            has_error = true;
+        }
+    }
+    if has_error {
+        std::process::exit(EXIT_CODE_ERROR);
+    }
+    let (days, file_by_date) = match read_days(
+        opt,
+        &work_files(opt, &check_opt.files),
+        opt.be_lenient,
+        None,
+    ) {
+        Some(days) => days,
+        None => std::process::exit(EXIT_CODE_ERROR),
+    };
+    if check_opt.compliance {
+        if print_compliance_report(&days, &file_by_date, opt) > 0 {
+            std::process::exit(EXIT_CODE_VIOLATIONS);
+        }
+    } else {
+        println!("{} file(s) without errors", file_by_date.len());
+    }
+}
+
+fn sync_jira(opt: &Opt, sync_opt: &SyncJiraOpt) {
+    let days = match read_days(opt, &work_files(opt, &sync_opt.files), false, None) {
+        Some((days, _)) => days,
+        None => std::process::exit(EXIT_CODE_ERROR),
+    };
+    let timezone = timezone(opt);
+    let jira_base_url = match opt.jira_base_url {
+        Some(ref jira_base_url) => jira_base_url.clone(),
+        None => {
+            println!("ERROR: Missing JIRA base URL");
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+    };
+    let credentials = log_work::credentials::jira_authentication(
+        opt.jira_username.clone(),
+        opt.jira_token.as_ref(),
+        opt.jira_password.as_ref(),
+        opt.jira_password_command.as_deref(),
+        &|| log_work::credentials::lookup_netrc(&jira_base_url),
+    );
+    let (jira_username, authentication) = match credentials {
+        Ok(credentials) => credentials,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+    };
+    let jira_config = log_work::jira::JiraConfig {
+        base_url: jira_base_url,
+        authentication,
+        username: jira_username.clone(),
+        timezone,
+        flavor: opt.jira_flavor.unwrap_or_default(),
+    };
+
+    let result = log_work::jira::update_logging_for_days(
+        &days.days.iter().map(|day| &day.work_day).collect(),
+        &jira_config,
+        &log_work::jira::SyncOptions {
+            dry_run: sync_opt.dry_run,
+            confirmation_policy: if sync_opt.yes {
+                log_work::jira::ConfirmationPolicy::Yes
+            } else if sync_opt.no_replace {
+                log_work::jira::ConfirmationPolicy::NoReplace
+            } else {
+                log_work::jira::ConfirmationPolicy::Ask
+            },
+        },
+    );
+    match result {
+        Ok(()) => {
+            if !sync_opt.dry_run {
+                println!("Successfully updated JIRA time logging");
+            }
+        }
+        Err(log_work::jira::Error::Canceled) => {
+            std::process::exit(EXIT_CODE_CANCELED);
+        }
+        Err(e) => {
             println!(
-                "{:20}:{:>20}",
-                key,
-                log_work::util::WorkDuration {
-                    duration_of_day,
-                    duration: *duration
-                }
+                "Sending the data to JIRA yielded the following result: {:?}",
+                e
             );
-            if key != "Pause" {
-                sum += *duration;
-            }
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+    }
+}
+
+fn new_work_file(opt: &Opt, new_opt: &NewOpt) {
+    let work_dir = opt
+        .work_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("."));
+    match log_work::work_file::create_work_file(
+        &work_dir,
+        &chrono::Local::now().date_naive(),
+        &new_opt.name,
+    ) {
+        Ok(file) => println!("{}", file.display()),
+        Err(e) => {
+            println!("ERROR: {}", e);
+            std::process::exit(EXIT_CODE_ERROR);
         }
-        println!(
-            "{:20}: {:>20}",
-            " == Required ==",
-            log_work::util::WorkDuration {
-                duration: sum_required,
-                duration_of_day
-            }
-        );
-        println!(
-            "{:20}: {:>20}",
-            " == Total ==",
-            log_work::util::WorkDuration {
-                duration_of_day,
-                duration: sum
-            }
-        );
     }
-    if opt.log_to_jira {
-        if opt.be_lenient {
-            println!("ERROR: Updating JIRA-logging is forbidden in lenient mode!");
+}
+
+/// Adds an entry to today's .work-file at the given time or now
+fn add_entry(opt: &Opt, at: Option<chrono::NaiveTime>, description: &str) {
+    let work_dir = opt
+        .work_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("."));
+    let now = chrono::Local::now().naive_local();
+    let time = at.unwrap_or_else(|| now.time());
+    let ts = now
+        .date()
+        .and_hms_opt(time.hour(), time.minute(), 0)
+        .expect("Invalid time");
+    match log_work::work_file::add_entry(&work_dir, &ts, description, &key_classes(opt)) {
+        Ok((file, line)) => println!("{}: {}", file.display(), line),
+        Err(e) => {
+            println!("ERROR: {}", e);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+    }
+}
+
+fn print_status(opt: &Opt, status_opt: &StatusOpt) {
+    let work_dir = opt
+        .work_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("."));
+    let now = chrono::Local::now().naive_local();
+    let today = now.date();
+    let key_classes = key_classes(opt);
+    let schedules = schedules(opt);
+    let work_day = log_work::work_file::find_work_file(&work_dir, &today).and_then(|file| {
+        let file = file.ok_or_else(|| {
+            log_work::Error::CommandLine(format!("No .work-file for {}", today.format("%F")))
+        })?;
+        log_work::work_day::WorkDay::parse_file(&file, false, Some(today), &key_classes)
+    });
+    let mut work_day = match work_day {
+        Ok(work_day) => work_day,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+    };
+    if let Some(ref fp) = opt.aliases {
+        log_work::alias::parse_alias_file(fp)
+            .expect("Error parsing alias file")
+            .apply_to_day(&mut work_day, &key_classes);
+    }
+    let required_time = log_work::required_time::consolidate_required_time(
+        &read_day_type_entries(opt, &today, &today),
+        &today,
+        &today,
+        &schedules,
+    )
+    .expect("Failed to consolidate required times");
+    let days = log_work::work_day::Days::join_work_and_requirement(
+        &std::iter::once((today, work_day)).collect(),
+        &required_time,
+        &schedules,
+    );
+    let day = days
+        .days
+        .iter()
+        .find(|day| day.work_day.date == today)
+        .expect("Missing today");
+    let status = log_work::status::status(day, &key_classes, &now);
+    if status_opt.short {
+        println!("{}", status.one_line());
+    } else {
+        println!("{}", status);
+    }
+}
+
+fn main() {
+    let opt_from_file = {
+        let mut rc_file = APP_INFO.config_dir().to_path_buf();
+        rc_file.push("log_work.rc");
+        if let Ok(f) = std::fs::File::open(&rc_file) {
+            let mut lines: Vec<String> = std::io::BufReader::new(f)
+                .lines()
+                .map(|e| e.unwrap())
+                .filter(|l| !l.is_empty())
+                .filter(|l| {
+                    // it was never taken from the rc-file, but older versions accepted it there
+                    if l.trim() == "--log_to_jira" {
+                        eprintln!(
+                            "WARNING: Ignoring '--log_to_jira' in {:?}, use 'sync jira' instead",
+                            rc_file
+                        );
+                        return false;
+                    }
+                    true
+                })
+                .collect();
+            lines.insert(0, "DUMMY".to_string()); // normally the first element holds the program name
+            Opt::from_iter(lines.iter())
         } else {
-            let timezone = if let Some(tz) = opt.timezone {
-                log_work::jira::TimeZone::Tz(tz)
-            } else {
-                log_work::jira::TimeZone::Local(chrono::Local)
-            };
-            let jira_username = opt.jira_username.expect("Missing JIRA username");
-            let jira_config = log_work::jira::JiraConfig {
-                base_url: opt.jira_base_url.expect("Missing JIRA base URL"),
-                basic_auth_credentials: Some((jira_username.clone(),
-                                         opt.jira_password.expect("Missing JIRA password"))),
-                username: jira_username.clone(),
-                timezone,
-            };
-
-            let result = log_work::jira::update_logging_for_days(
-                &days.days.iter().map(|day| &day.work_day).collect(),
-                &jira_config,
-            );
-            match result {
-                Ok(()) => {
-                    println!("Successfully updated JIRA time logging");
-                }
-                Err(e) => {
-                    println!(
-                        "Sending the data to JIRA yielded the following result: {:?}",
-                        e
-                    );
-                }
-            }
+            Opt::default()
+        }
+    };
+    let opt_from_args = Opt::from_args();
+
+    if opt_from_args.debug || opt_from_file.debug {
+        println!("Application directory: {:?}", APP_INFO.config_dir());
+        println!("file={:?} cmd={:?}", opt_from_file, opt_from_args);
+    }
+    // the subcommand is only taken from the command line, older versions always printed the report
+    let command = opt_from_args
+        .command
+        .unwrap_or_else(|| Command::Report(ReportOpt::default()));
+    let opt = Opt {
+        holidays: first_available(opt_from_args.holidays, opt_from_file.holidays),
+        ics: first_non_empty(opt_from_args.ics, opt_from_file.ics),
+        ics_rule: first_non_empty(opt_from_args.ics_rule, opt_from_file.ics_rule),
+        holiday_region: first_available(opt_from_args.holiday_region, opt_from_file.holiday_region),
+        aliases: first_available(opt_from_args.aliases, opt_from_file.aliases),
+        debug: opt_from_args.debug || opt_from_file.debug,
+        verbose: opt_from_args.verbose || opt_from_file.verbose,
+        be_lenient: opt_from_args.be_lenient || opt_from_file.be_lenient,
+        format: first_available(opt_from_args.format, opt_from_file.format),
+        csv_delimiter: first_available(opt_from_args.csv_delimiter, opt_from_file.csv_delimiter),
+        decimal_separator: first_available(
+            opt_from_args.decimal_separator,
+            opt_from_file.decimal_separator,
+        ),
+        group_by: first_available(opt_from_args.group_by, opt_from_file.group_by),
+        break_keys: first_non_empty(opt_from_args.break_keys, opt_from_file.break_keys),
+        excluded_keys: first_non_empty(opt_from_args.excluded_keys, opt_from_file.excluded_keys),
+        duration_of_day: first_available(
+            opt_from_args.duration_of_day,
+            opt_from_file.duration_of_day,
+        ),
+        schedule: first_non_empty(opt_from_args.schedule, opt_from_file.schedule),
+        balance_start_date: first_available(
+            opt_from_args.balance_start_date,
+            opt_from_file.balance_start_date,
+        ),
+        opening_balance: first_available(
+            opt_from_args.opening_balance,
+            opt_from_file.opening_balance,
+        ),
+        vacation_days: first_non_empty(opt_from_args.vacation_days, opt_from_file.vacation_days),
+        vacation_carry_over: first_available(
+            opt_from_args.vacation_carry_over,
+            opt_from_file.vacation_carry_over,
+        ),
+        carry_over_expiry: first_available(
+            opt_from_args.carry_over_expiry,
+            opt_from_file.carry_over_expiry,
+        ),
+        max_daily_work: first_available(opt_from_args.max_daily_work, opt_from_file.max_daily_work),
+        break_rule: first_non_empty(opt_from_args.break_rule, opt_from_file.break_rule),
+        max_work_without_break: first_available(
+            opt_from_args.max_work_without_break,
+            opt_from_file.max_work_without_break,
+        ),
+        min_break: first_available(opt_from_args.min_break, opt_from_file.min_break),
+        min_rest: first_available(opt_from_args.min_rest, opt_from_file.min_rest),
+        allow_rest_day_work: opt_from_args.allow_rest_day_work || opt_from_file.allow_rest_day_work,
+        work_dir: first_available(opt_from_args.work_dir, opt_from_file.work_dir),
+        timezone: first_available(opt_from_args.timezone, opt_from_file.timezone),
+        jira_base_url: first_available(opt_from_args.jira_base_url, opt_from_file.jira_base_url),
+        jira_flavor: first_available(opt_from_args.jira_flavor, opt_from_file.jira_flavor),
+        jira_username: first_available(opt_from_args.jira_username, opt_from_file.jira_username),
+        jira_password: first_available(opt_from_args.jira_password, opt_from_file.jira_password),
+        jira_password_command: first_available(
+            opt_from_args.jira_password_command,
+            opt_from_file.jira_password_command,
+        ),
+        jira_token: first_available(opt_from_args.jira_token, opt_from_file.jira_token),
+        files: [opt_from_args.files, opt_from_file.files].concat(),
+        command: None,
+    };
+
+    if opt.debug {
+        println!("opt={:?} command={:?}", opt, command);
+    }
+    match command {
+        Command::Report(ref report_opt) => report(&opt, report_opt),
+        Command::Check(ref check_opt) => check(&opt, check_opt),
+        Command::Sync {
+            target: SyncTarget::Jira(ref sync_opt),
+        } => sync_jira(&opt, sync_opt),
+        Command::New(ref new_opt) => new_work_file(&opt, new_opt),
+        Command::Start(ref start_opt) => {
+            let mut description = vec![start_opt.key.clone()];
+            description.extend(start_opt.sub_keys.iter().cloned());
+            add_entry(&opt, start_opt.at, &description.join(" "))
         }
+        Command::Status(ref status_opt) => print_status(&opt, status_opt),
+        Command::Pause(ref pause_opt) => add_entry(
+            &opt,
+            pause_opt.at,
+            opt.break_keys.first().map_or("Pause", |key| key.as_str()),
+        ),
     }
 }